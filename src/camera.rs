@@ -4,12 +4,49 @@
 //     write_color, Color, Point3, Vec3, Vector,
 // };
 use crate::math::base::degrees_to_radians;
-use crate::math::rand::random_in_unit_disk;
-use crate::math::vec3::{cross, unit_vector, Point3, Vec3, Vector};
+use crate::math::constants::{INFINITY, PI};
+use crate::math::quaternion::Quaternion;
+use crate::math::rand::{random_f64, random_in_unit_disk};
+use crate::math::vec3::{cross, dot, unit_vector, vec3, Point3, Vec3, Vector};
 
 use crate::{new_ray, Ray};
 
+/// How a camera turns image coordinates into rays.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Projection {
+    /// Rays spread out from the lens through a viewport set by `vfov`.
+    Perspective,
+    /// Parallel rays along the view direction, from a viewport `view_width`
+    /// units across, so parallel lines stay parallel. Has no depth of field.
+    Orthographic { view_width: f64 },
+    /// Full 360° by 180° panorama: longitude runs across the image, with the
+    /// view direction in the middle, and latitude up it. Best at 2:1.
+    Equirectangular,
+    /// Angular (equidistant) fisheye: the distance from the image centre is
+    /// proportional to the angle off the view direction, reaching `fov / 2`
+    /// degrees at the edge of a circle as tall as the image. Best at 1:1.
+    Fisheye { fov: f64 },
+    /// The six 90° faces of a cube map in a 3:2 atlas, `+X -X +Y` on the top
+    /// row and `-Y +Z -Z` below, along the camera's right, up and backward
+    /// axes. A camera looking down -Z with +Y up renders world-aligned faces.
+    CubeMap,
+}
+
+/// View and up directions of each cube map face, in atlas order, in the
+/// camera's (right, up, backward) frame.
+fn cube_face_axes(face: usize) -> (Vec3, Vec3) {
+    match face {
+        0 => (vec3(1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0)),
+        1 => (vec3(-1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0)),
+        2 => (vec3(0.0, 1.0, 0.0), vec3(0.0, 0.0, 1.0)),
+        3 => (vec3(0.0, -1.0, 0.0), vec3(0.0, 0.0, -1.0)),
+        4 => (vec3(0.0, 0.0, 1.0), vec3(0.0, 1.0, 0.0)),
+        _ => (vec3(0.0, 0.0, -1.0), vec3(0.0, 1.0, 0.0)),
+    }
+}
+
 #[allow(dead_code)]
+#[derive(Clone)]
 pub struct Camera {
     aspect_ratio: f64,
     viewport_height: f64,
@@ -25,6 +62,15 @@ pub struct Camera {
     w: Vec3,
     v: Vec3,
     u: Vec3,
+    /// Shutter open and close times; rays are spread evenly between them.
+    time0: f64,
+    time1: f64,
+    projection: Projection,
+    focus_dist: f64,
+    /// Sideways offset of the eye this camera renders for in a stereo pair,
+    /// and the distance at which both eyes' views agree.
+    eye_offset: f64,
+    convergence: f64,
 }
 
 impl Camera {
@@ -68,18 +114,289 @@ impl Camera {
             w,
             v,
             u,
+            time0: 0.0,
+            time1: 0.0,
+            projection: Projection::Perspective,
+            focus_dist,
+            eye_offset: 0.0,
+            convergence: INFINITY,
         }
     }
 
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd = self.lens_radius * random_in_unit_disk();
-        let offset = self.u * rd.x() + self.v * rd.y();
+    /// Camera at `lookfrom` looking down its -Z axis turned by `rotation`,
+    /// with its +Y axis as the up direction.
+    pub fn from_rotation(
+        lookfrom: Point3,
+        rotation: &Quaternion,
+        vfov: f64,
+        aspect_ratio: f64,
+        aperture: f64,
+        focus_dist: f64,
+    ) -> Self {
+        let forward = rotation.rotate(&vec3(0.0, 0.0, -1.0));
+        let vup = rotation.rotate(&vec3(0.0, 1.0, 0.0));
+        Camera::new(
+            lookfrom,
+            lookfrom + forward,
+            vup,
+            vfov,
+            aspect_ratio,
+            aperture,
+            focus_dist,
+        )
+    }
+
+    /// Keeps the shutter open from `time0` to `time1`, blurring anything
+    /// that moves in between. By default it opens and closes at time 0.
+    pub fn with_shutter(mut self, time0: f64, time1: f64) -> Self {
+        self.time0 = time0;
+        self.time1 = time1;
+        self
+    }
+
+    /// Switches to an orthographic projection showing `view_width` units
+    /// across, centred on the view direction. `vfov` no longer matters.
+    pub fn with_orthographic(mut self, view_width: f64) -> Self {
+        self.projection = Projection::Orthographic { view_width };
+        self
+    }
+
+    /// Switches to a 360° equirectangular panorama around the camera.
+    pub fn with_equirectangular(mut self) -> Self {
+        self.projection = Projection::Equirectangular;
+        self
+    }
+
+    /// Switches to an angular fisheye covering `fov` degrees, up to 360.
+    pub fn with_fisheye(mut self, fov: f64) -> Self {
+        self.projection = Projection::Fisheye { fov };
+        self
+    }
+
+    /// Switches to rendering the six faces of a cube map around the camera.
+    pub fn with_cube_map(mut self) -> Self {
+        self.projection = Projection::CubeMap;
+        self
+    }
+
+    pub fn aspect_ratio(&self) -> f64 {
+        self.aspect_ratio
+    }
 
-        return new_ray(
-            &(self.origin + offset),
-            &(self.lower_left_corner + s * self.horizontal + t * self.vertical
-                - self.origin
-                - offset),
-        );
+    /// Turns a direction from the camera's (right, up, backward) frame into
+    /// world space.
+    fn to_world(&self, x: f64, y: f64, z: f64) -> Vec3 {
+        x * self.u + y * self.v + z * self.w
+    }
+
+    /// Face of the cube map atlas at `(s, t)`, and the position on that face
+    /// in [-1, 1] squared.
+    fn cube_face(s: f64, t: f64) -> (usize, f64, f64) {
+        let column = ((3.0 * s) as usize).min(2);
+        let row = if t >= 0.5 { 0 } else { 1 };
+        let a = 2.0 * (3.0 * s - column as f64) - 1.0;
+        let b = 2.0 * (2.0 * t - (1 - row) as f64) - 1.0;
+        (3 * row + column, a, b)
+    }
+
+    /// Inverse of `get_ray` without a lens: the `(s, t)` image coordinates
+    /// `p` appears at, or `None` if the camera can't see it.
+    pub fn project(&self, p: &Point3) -> Option<(f64, f64)> {
+        let d = p - self.origin;
+        let (x, y, z) = (dot(&d, &self.u), dot(&d, &self.v), dot(&d, &self.w));
+        match self.projection {
+            Projection::Perspective | Projection::Orthographic { .. } if z >= 0.0 => None,
+            Projection::Perspective => {
+                let s = 0.5 + x / (-z * self.viewport_width);
+                let t = 0.5 + y / (-z * self.viewport_height);
+                Some((s, t))
+            }
+            Projection::Orthographic { view_width } => {
+                let view_height = view_width / self.aspect_ratio;
+                Some((0.5 + x / view_width, 0.5 + y / view_height))
+            }
+            Projection::Equirectangular => {
+                let longitude = x.atan2(-z);
+                let latitude = (y / d.length()).clamp(-1.0, 1.0).asin();
+                Some((0.5 + longitude / (2.0 * PI), 0.5 + latitude / PI))
+            }
+            Projection::Fisheye { fov } => {
+                let angle = (-z / d.length()).clamp(-1.0, 1.0).acos();
+                let r = angle / degrees_to_radians(0.5 * fov);
+                if r > 1.0 {
+                    return None;
+                }
+                let sideways = x.hypot(y);
+                let (dx, dy) = if sideways > 0.0 {
+                    (x / sideways, y / sideways)
+                } else {
+                    (0.0, 0.0)
+                };
+                let s = 0.5 + 0.5 * r * dx / self.aspect_ratio;
+                let t = 0.5 + 0.5 * r * dy;
+                Some((s, t))
+            }
+            Projection::CubeMap => {
+                let local = vec3(x, y, z);
+                let (face, forward) = (0..6)
+                    .map(|face| (face, dot(&local, &cube_face_axes(face).0)))
+                    .fold((0, f64::NEG_INFINITY), |best, candidate| {
+                        if candidate.1 > best.1 {
+                            candidate
+                        } else {
+                            best
+                        }
+                    });
+                if forward <= 0.0 {
+                    return None;
+                }
+                let (f, up) = cube_face_axes(face);
+                let right = cross(&f, &up);
+                let a = dot(&local, &right) / forward;
+                let b = dot(&local, &up) / forward;
+                let (row, column) = (face / 3, face % 3);
+                let s = (column as f64 + 0.5 * (a + 1.0)) / 3.0;
+                let t = ((1 - row) as f64 + 0.5 * (b + 1.0)) / 2.0;
+                Some((s, t))
+            }
+        }
+    }
+
+    /// Ray through image coordinates `(s, t)`, both in [0, 1] from the bottom
+    /// left. `None` where the projection shows nothing, outside a fisheye's
+    /// circle. Only the perspective projection has depth of field.
+    pub fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        let time = if self.time1 > self.time0 {
+            random_f64(self.time0, self.time1)
+        } else {
+            self.time0
+        };
+
+        // Stereo eyes sit `eye_offset` to the right of where the camera is,
+        // turned in to meet at the convergence distance. Planar projections
+        // shift the whole view sideways, panoramic ones give every ray its
+        // own eye on a circle around the camera (omnidirectional stereo).
+        let skew = self.eye_offset / self.convergence;
+        let (origin, direction) = match self.projection {
+            Projection::Perspective => {
+                let eye = self.origin + self.eye_offset * self.u;
+                let target = self.lower_left_corner
+                    + s * self.horizontal
+                    + t * self.vertical
+                    + (self.eye_offset - skew * self.focus_dist) * self.u;
+                let rd = self.lens_radius * random_in_unit_disk();
+                let offset = self.u * rd.x() + self.v * rd.y();
+                return Some(new_ray(&(eye + offset), &(target - eye - offset), time));
+            }
+            Projection::Orthographic { view_width } => {
+                let view_height = view_width / self.aspect_ratio;
+                let offset = (s - 0.5) * view_width * self.u + (t - 0.5) * view_height * self.v;
+                return Some(new_ray(
+                    &(self.origin + offset + self.eye_offset * self.u),
+                    &(-self.w - skew * self.u),
+                    time,
+                ));
+            }
+            Projection::Equirectangular => {
+                let longitude = (s - 0.5) * 2.0 * PI;
+                let latitude = (t - 0.5) * PI;
+                let direction = self.to_world(
+                    latitude.cos() * longitude.sin(),
+                    latitude.sin(),
+                    -latitude.cos() * longitude.cos(),
+                );
+                (self.origin, direction)
+            }
+            Projection::Fisheye { fov } => {
+                let x = 2.0 * (s - 0.5) * self.aspect_ratio;
+                let y = 2.0 * (t - 0.5);
+                let r = x.hypot(y);
+                if r > 1.0 {
+                    return None;
+                }
+                let angle = r * degrees_to_radians(0.5 * fov);
+                let (dx, dy) = if r > 0.0 { (x / r, y / r) } else { (0.0, 0.0) };
+                let direction = self.to_world(angle.sin() * dx, angle.sin() * dy, -angle.cos());
+                (self.origin, direction)
+            }
+            Projection::CubeMap => {
+                let (face, a, b) = Camera::cube_face(s, t);
+                let (f, up) = cube_face_axes(face);
+                let local = f + a * cross(&f, &up) + b * up;
+                let direction = unit_vector(self.to_world(local.x(), local.y(), local.z()));
+                (self.origin, direction)
+            }
+        };
+        if self.eye_offset == 0.0 {
+            return Some(new_ray(&origin, &direction, time));
+        }
+        // Shrinks towards the poles, where the eyes' circle collapses.
+        let right = cross(&direction, &self.v);
+        Some(new_ray(
+            &(origin + self.eye_offset * right),
+            &(direction - skew * right),
+            time,
+        ))
+    }
+}
+
+/// How the two eyes' images share a stereo frame.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StereoLayout {
+    /// Left eye in the left half, right eye in the right half.
+    SideBySide,
+    /// Left eye in the top half, right eye in the bottom half.
+    OverUnder,
+}
+
+/// A pair of eyes rendering a scene into one stereo frame. Both eyes share
+/// the settings of the camera they were made from, including its projection:
+/// a perspective or orthographic camera gives a regular stereo pair and a
+/// panoramic one an omnidirectional stereo panorama for VR.
+pub struct StereoCamera {
+    left: Camera,
+    right: Camera,
+    layout: StereoLayout,
+}
+
+impl StereoCamera {
+    /// Places the eyes `interpupillary_distance` apart around `camera`,
+    /// turned in so objects `convergence` units away appear at screen depth.
+    /// An infinite `convergence` keeps the eyes parallel.
+    pub fn new(
+        camera: Camera,
+        interpupillary_distance: f64,
+        convergence: f64,
+        layout: StereoLayout,
+    ) -> Self {
+        let eye = |offset: f64| Camera {
+            eye_offset: offset,
+            convergence,
+            ..camera.clone()
+        };
+        StereoCamera {
+            left: eye(-0.5 * interpupillary_distance),
+            right: eye(0.5 * interpupillary_distance),
+            layout,
+        }
+    }
+
+    /// Aspect ratio of the whole frame, with both eyes in it.
+    pub fn aspect_ratio(&self) -> f64 {
+        match self.layout {
+            StereoLayout::SideBySide => 2.0 * self.left.aspect_ratio,
+            StereoLayout::OverUnder => 0.5 * self.left.aspect_ratio,
+        }
+    }
+
+    /// Ray through `(s, t)` of the whole frame, from whichever eye's image
+    /// that point falls in.
+    pub fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        match self.layout {
+            StereoLayout::SideBySide if s < 0.5 => self.left.get_ray(2.0 * s, t),
+            StereoLayout::SideBySide => self.right.get_ray(2.0 * s - 1.0, t),
+            StereoLayout::OverUnder if t >= 0.5 => self.left.get_ray(s, 2.0 * t - 1.0),
+            StereoLayout::OverUnder => self.right.get_ray(s, 2.0 * t),
+        }
     }
 }
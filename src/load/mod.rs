@@ -0,0 +1,503 @@
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+
+use crate::math::vec3::{color, vec3, ColorRGB};
+use crate::shapes::tri_mesh::MeshData;
+
+/// A decoded image with linear colour values in [0, 1], stored row by row from the top.
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<ColorRGB>,
+}
+
+impl Image {
+    pub fn pixel(&self, i: usize, j: usize) -> ColorRGB {
+        self.pixels[j * self.width + i]
+    }
+}
+
+/// Dense grid of scalar values, stored with x varying fastest, then y, then z.
+pub struct VoxelGrid {
+    pub nx: usize,
+    pub ny: usize,
+    pub nz: usize,
+    pub values: Vec<f32>,
+}
+
+impl VoxelGrid {
+    pub fn value(&self, i: usize, j: usize, k: usize) -> f32 {
+        self.values[(k * self.ny + j) * self.nx + i]
+    }
+}
+
+/// Regular grid of heights, stored row by row with x varying fastest.
+pub struct HeightGrid {
+    pub nx: usize,
+    pub nz: usize,
+    pub values: Vec<f32>,
+}
+
+impl HeightGrid {
+    pub fn value(&self, i: usize, k: usize) -> f32 {
+        self.values[k * self.nx + i]
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+/// Raw samples of a netpbm file, before any colour conversion.
+struct PnmSamples {
+    width: usize,
+    height: usize,
+    channels: usize,
+    max_value: usize,
+    samples: Vec<usize>,
+}
+
+/// Parses a netpbm file: P2/P5 greymaps or P3/P6 pixmaps with a maxval of up to 65535.
+fn read_pnm_samples(path: &str) -> Result<PnmSamples> {
+    let bytes = fs::read(path)?;
+    let mut pos = 0;
+
+    // Header tokens are whitespace separated and may be interleaved with `#` comments.
+    let mut next_token = |bytes: &[u8]| -> Result<String> {
+        loop {
+            while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if pos < bytes.len() && bytes[pos] == b'#' {
+                while pos < bytes.len() && bytes[pos] != b'\n' {
+                    pos += 1;
+                }
+                continue;
+            }
+            break;
+        }
+        let start = pos;
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if start == pos {
+            return Err(invalid("unexpected end of image"));
+        }
+        Ok(String::from_utf8_lossy(&bytes[start..pos]).into_owned())
+    };
+    let parse = |token: String| -> Result<usize> {
+        token
+            .parse()
+            .map_err(|_| invalid("malformed number in image"))
+    };
+
+    let magic = next_token(&bytes)?;
+    let (channels, binary) = match magic.as_str() {
+        "P2" => (1, false),
+        "P3" => (3, false),
+        "P5" => (1, true),
+        "P6" => (3, true),
+        _ => return Err(invalid("unsupported image format")),
+    };
+    let width = parse(next_token(&bytes)?)?;
+    let height = parse(next_token(&bytes)?)?;
+    let max_value = parse(next_token(&bytes)?)?;
+    if max_value == 0 || max_value > 65535 {
+        return Err(invalid("invalid maximum value in image"));
+    }
+
+    let count = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(channels))
+        .ok_or_else(|| invalid("image is too large"))?;
+    // The file must be able to hold that many samples before any room is
+    // allocated for them.
+    let samples = if binary {
+        // Exactly one whitespace byte separates the header from the raster.
+        let start = pos + 1;
+        let sample_size = if max_value < 256 { 1 } else { 2 };
+        let raster_size = count.checked_mul(sample_size);
+        if raster_size.is_none_or(|size| size > bytes.len().saturating_sub(start)) {
+            return Err(invalid("image data is truncated"));
+        }
+        (0..count)
+            .map(|k| {
+                let offset = start + k * sample_size;
+                if sample_size == 1 {
+                    bytes[offset] as usize
+                } else {
+                    ((bytes[offset] as usize) << 8) | bytes[offset + 1] as usize
+                }
+            })
+            .collect()
+    } else {
+        // Every plain sample takes at least one digit.
+        if count > bytes.len() {
+            return Err(invalid("image data is truncated"));
+        }
+        let mut samples = Vec::with_capacity(count);
+        for _ in 0..count {
+            samples.push(parse(next_token(&bytes)?)?);
+        }
+        samples
+    };
+
+    Ok(PnmSamples {
+        width,
+        height,
+        channels,
+        max_value,
+        samples,
+    })
+}
+
+/// Reads a netpbm image. Values are converted back to linear with the same
+/// gamma 2 `save_color` applies.
+pub fn read_pnm(path: &str) -> Result<Image> {
+    let PnmSamples {
+        width,
+        height,
+        channels,
+        max_value,
+        samples,
+    } = read_pnm_samples(path)?;
+
+    let to_linear = |value: usize| {
+        let encoded = value as f64 / max_value as f64;
+        encoded * encoded
+    };
+    let pixels = samples
+        .chunks(channels)
+        .map(|pixel| {
+            if channels == 1 {
+                let grey = to_linear(pixel[0]);
+                color(grey, grey, grey)
+            } else {
+                color(
+                    to_linear(pixel[0]),
+                    to_linear(pixel[1]),
+                    to_linear(pixel[2]),
+                )
+            }
+        })
+        .collect();
+
+    Ok(Image {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Reads a raw voxel grid: a header of three little-endian `u32` resolutions
+/// (x, y, z) followed by that many little-endian `f32` values, x fastest.
+pub fn read_voxel_grid(path: &str) -> Result<VoxelGrid> {
+    let bytes = fs::read(path)?;
+    if bytes.len() < 12 {
+        return Err(invalid("voxel grid header is truncated"));
+    }
+    let read_u32 = |offset: usize| {
+        u32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ]) as usize
+    };
+    let (nx, ny, nz) = (read_u32(0), read_u32(4), read_u32(8));
+    if nx == 0 || ny == 0 || nz == 0 {
+        return Err(invalid("voxel grid has no voxels"));
+    }
+    let size = nx
+        .checked_mul(ny)
+        .and_then(|count| count.checked_mul(nz))
+        .and_then(|count| count.checked_mul(4))
+        .ok_or_else(|| invalid("voxel grid is too large"))?;
+    if bytes.len() - 12 < size {
+        return Err(invalid("voxel grid data is truncated"));
+    }
+
+    let values = bytes[12..12 + size]
+        .chunks(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    Ok(VoxelGrid { nx, ny, nz, values })
+}
+
+/// Reads a height map from a netpbm image. Heights are the stored values
+/// scaled to [0, 1], with no gamma applied; colour channels are averaged.
+/// Images smaller than 2x2 are rejected, as they can't make a heightfield.
+pub fn read_height_map(path: &str) -> Result<HeightGrid> {
+    let pnm = read_pnm_samples(path)?;
+    if pnm.width < 2 || pnm.height < 2 {
+        return Err(invalid("height map needs at least 2x2 pixels"));
+    }
+    let values = pnm
+        .samples
+        .chunks(pnm.channels)
+        .map(|pixel| {
+            let sum: usize = pixel.iter().sum();
+            (sum as f64 / (pnm.channels * pnm.max_value) as f64) as f32
+        })
+        .collect();
+    Ok(HeightGrid {
+        nx: pnm.width,
+        nz: pnm.height,
+        values,
+    })
+}
+
+/// Reads a raw height grid: a header of two little-endian `u32` resolutions
+/// (x, z) followed by that many little-endian `f32` heights, x fastest.
+/// Like `read_height_map`, it needs at least 2x2 heights.
+pub fn read_height_grid(path: &str) -> Result<HeightGrid> {
+    let bytes = fs::read(path)?;
+    if bytes.len() < 8 {
+        return Err(invalid("height grid header is truncated"));
+    }
+    let read_u32 = |offset: usize| {
+        u32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ]) as usize
+    };
+    let (nx, nz) = (read_u32(0), read_u32(4));
+    if nx < 2 || nz < 2 {
+        return Err(invalid("height grid needs at least 2x2 samples"));
+    }
+    let size = nx
+        .checked_mul(nz)
+        .and_then(|count| count.checked_mul(4))
+        .ok_or_else(|| invalid("height grid is too large"))?;
+    if bytes.len() - 8 < size {
+        return Err(invalid("height grid data is truncated"));
+    }
+
+    let values = bytes[8..8 + size]
+        .chunks(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    Ok(HeightGrid { nx, nz, values })
+}
+
+/// Position, texture coordinate and normal indices of an OBJ face corner.
+type ObjCorner = (usize, Option<usize>, Option<usize>);
+
+/// Reads the geometry of a Wavefront OBJ file: `v`, `vt` and `vn` lines and
+/// polygon `f` lines, which are split into triangle fans. Everything else
+/// (groups, materials, smoothing) is skipped.
+///
+/// Meshes are indexed by position, so each position keeps the texture
+/// coordinate and normal of the first face corner using it. That suits
+/// subdivision cages, which must stay connected across UV seams.
+pub fn read_obj(path: &str) -> Result<MeshData> {
+    let text = fs::read_to_string(path)?;
+    let mut positions = Vec::new();
+    let mut file_uvs = Vec::new();
+    let mut file_normals = Vec::new();
+    let mut corners: Vec<[ObjCorner; 3]> = Vec::new();
+
+    let number = |token: Option<&str>| -> Result<f64> {
+        token
+            .and_then(|t| t.parse().ok())
+            .ok_or_else(|| invalid("malformed number in OBJ file"))
+    };
+    // OBJ indices start at 1, and negative ones count back from the end.
+    let index = |token: &str, count: usize| -> Result<usize> {
+        let i: i64 = token
+            .parse()
+            .map_err(|_| invalid("malformed index in OBJ file"))?;
+        let resolved = if i < 0 { count as i64 + i } else { i - 1 };
+        if resolved < 0 || resolved as usize >= count {
+            return Err(invalid("index out of range in OBJ file"));
+        }
+        Ok(resolved as usize)
+    };
+
+    for line in text.lines() {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => positions.push(vec3(
+                number(tokens.next())?,
+                number(tokens.next())?,
+                number(tokens.next())?,
+            )),
+            Some("vt") => file_uvs.push((number(tokens.next())?, number(tokens.next())?)),
+            Some("vn") => file_normals.push(vec3(
+                number(tokens.next())?,
+                number(tokens.next())?,
+                number(tokens.next())?,
+            )),
+            Some("f") => {
+                let mut face = Vec::new();
+                for corner in tokens {
+                    let mut parts = corner.split('/');
+                    let v = index(parts.next().unwrap_or(""), positions.len())?;
+                    let vt = match parts.next() {
+                        Some(t) if !t.is_empty() => Some(index(t, file_uvs.len())?),
+                        _ => None,
+                    };
+                    let vn = match parts.next() {
+                        Some(t) if !t.is_empty() => Some(index(t, file_normals.len())?),
+                        _ => None,
+                    };
+                    face.push((v, vt, vn));
+                }
+                if face.len() < 3 {
+                    return Err(invalid("OBJ face has fewer than 3 corners"));
+                }
+                for k in 1..face.len() - 1 {
+                    corners.push([face[0], face[k], face[k + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut uvs = vec![None; positions.len()];
+    let mut normals = vec![None; positions.len()];
+    for &(v, vt, vn) in corners.iter().flatten() {
+        if uvs[v].is_none() {
+            uvs[v] = vt.map(|i| file_uvs[i]);
+        }
+        if normals[v].is_none() {
+            normals[v] = vn.map(|i| file_normals[i]);
+        }
+    }
+
+    Ok(MeshData {
+        indices: corners
+            .iter()
+            .map(|triangle| triangle.map(|(v, _, _)| v))
+            .collect(),
+        positions,
+        // Only kept if every position got one.
+        normals: normals.into_iter().collect(),
+        uvs: uvs.into_iter().collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `bytes` to a file in the temp directory and returns its path.
+    fn fixture(name: &str, bytes: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("raytracing-{}-{}", std::process::id(), name));
+        fs::write(&path, bytes).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn reads_plain_pixmap() {
+        let path = fixture("plain.ppm", b"P3\n# comment\n2 1\n255\n255 0 0  0 0 255\n");
+        let image = read_pnm(&path).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixel(0, 0), color(1.0, 0.0, 0.0));
+        assert_eq!(image.pixel(1, 0), color(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn reads_binary_pixmap_as_linear() {
+        let mut bytes = b"P6 1 2 255\n".to_vec();
+        bytes.extend_from_slice(&[255, 255, 255, 0, 0, 0]);
+        let image = read_pnm(&fixture("binary.ppm", &bytes)).unwrap();
+        assert_eq!(image.pixel(0, 0), color(1.0, 1.0, 1.0));
+        assert_eq!(image.pixel(0, 1), color(0.0, 0.0, 0.0));
+
+        // Stored values are gamma 2 encoded.
+        let bytes = [b"P6 1 1 100\n".as_slice(), &[50, 50, 50]].concat();
+        let image = read_pnm(&fixture("gamma.ppm", &bytes)).unwrap();
+        assert_eq!(image.pixel(0, 0), color(0.25, 0.25, 0.25));
+    }
+
+    #[test]
+    fn rejects_truncated_or_oversized_pixmaps() {
+        let bytes = [b"P6 2 2 255\n".as_slice(), &[0; 11]].concat();
+        assert!(read_pnm(&fixture("short.ppm", &bytes)).is_err());
+        assert!(read_pnm(&fixture("short-plain.ppm", b"P3 1 1 255 0 0")).is_err());
+        let huge = format!("P6 {} {} 255\n", usize::MAX / 2, 3);
+        assert!(read_pnm(&fixture("huge.ppm", huge.as_bytes())).is_err());
+    }
+
+    /// Raw grid file: little-endian `u32` resolutions, then `f32` values.
+    fn grid_bytes(resolution: &[u32], values: &[f32]) -> Vec<u8> {
+        let header = resolution.iter().flat_map(|n| n.to_le_bytes());
+        header
+            .chain(values.iter().flat_map(|v| v.to_le_bytes()))
+            .collect()
+    }
+
+    #[test]
+    fn voxel_grid_round_trips() {
+        let values: Vec<f32> = (0..24).map(|i| i as f32 * 0.5).collect();
+        let path = fixture("grid.raw", &grid_bytes(&[2, 3, 4], &values));
+        let grid = read_voxel_grid(&path).unwrap();
+        assert_eq!((grid.nx, grid.ny, grid.nz), (2, 3, 4));
+        assert_eq!(grid.values, values);
+        // x varies fastest, then y, then z.
+        assert_eq!(grid.value(1, 2, 3), values[(3 * 3 + 2) * 2 + 1]);
+    }
+
+    #[test]
+    fn rejects_bad_voxel_grids() {
+        let truncated = grid_bytes(&[2, 2, 2], &[1.0; 7]);
+        assert!(read_voxel_grid(&fixture("truncated.raw", &truncated)).is_err());
+        assert!(read_voxel_grid(&fixture("header.raw", &[0; 11])).is_err());
+        let empty = grid_bytes(&[4, 0, 4], &[]);
+        assert!(read_voxel_grid(&fixture("empty.raw", &empty)).is_err());
+        let huge = grid_bytes(&[u32::MAX, u32::MAX, u32::MAX], &[1.0]);
+        assert!(read_voxel_grid(&fixture("huge.raw", &huge)).is_err());
+    }
+
+    #[test]
+    fn reads_height_maps_and_grids() {
+        let map = read_height_map(&fixture("hills.pgm", b"P2 2 2 4\n0 1\n2 4\n")).unwrap();
+        assert_eq!((map.nx, map.nz), (2, 2));
+        assert_eq!(map.values, vec![0.0, 0.25, 0.5, 1.0]);
+
+        let heights = [0.0, 0.5, 1.0, 1.5, 2.0, 2.5];
+        let grid = read_height_grid(&fixture("ridge.raw", &grid_bytes(&[3, 2], &heights))).unwrap();
+        assert_eq!((grid.nx, grid.nz), (3, 2));
+        assert_eq!(grid.value(2, 1), 2.5);
+    }
+
+    #[test]
+    fn rejects_heights_too_small_for_a_heightfield() {
+        assert!(read_height_map(&fixture("line.pgm", b"P2 3 1 9\n1 2 3\n")).is_err());
+        let line = grid_bytes(&[1, 4], &[0.0; 4]);
+        assert!(read_height_grid(&fixture("line.raw", &line)).is_err());
+        let truncated = grid_bytes(&[2, 2], &[0.0; 3]);
+        assert!(read_height_grid(&fixture("short-grid.raw", &truncated)).is_err());
+    }
+
+    #[test]
+    fn splits_obj_polygons_into_fans() {
+        let obj = b"v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv -1 1 0\nf 1 2 3 4 5\n";
+        let mesh = read_obj(&fixture("fan.obj", obj)).unwrap();
+        assert_eq!(mesh.positions.len(), 5);
+        assert_eq!(mesh.indices, vec![[0, 1, 2], [0, 2, 3], [0, 3, 4]]);
+    }
+
+    #[test]
+    fn resolves_negative_obj_indices() {
+        let obj =
+            b"v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf -3//-1 -2//-1 -1//-1\nv 1 1 0\nf -3 -2 -1\n";
+        let mesh = read_obj(&fixture("negative.obj", obj)).unwrap();
+        assert_eq!(mesh.indices, vec![[0, 1, 2], [1, 2, 3]]);
+        // The last vertex has no normal, so the mesh keeps none.
+        assert!(mesh.normals.is_none());
+    }
+
+    #[test]
+    fn rejects_out_of_range_obj_indices() {
+        let obj = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n";
+        assert!(read_obj(&fixture("past-end.obj", obj)).is_err());
+        let obj = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf -4 -2 -1\n";
+        assert!(read_obj(&fixture("before-start.obj", obj)).is_err());
+        let obj = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 0 1 2\n";
+        assert!(read_obj(&fixture("zero.obj", obj)).is_err());
+    }
+}
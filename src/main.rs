@@ -1,31 +1,34 @@
-#[allow(dead_code)]
+#![allow(clippy::needless_return)]
+
 use std::fs::File;
 use std::io::{BufWriter, Error, Write};
 
-use std::sync::Arc;
 use std::time::Instant;
 
 mod math;
 use math::rand::random_f64;
-use math::ray::{new_ray, ray_color, Ray};
-use math::vec3::{color, point, vec3, ColorRGB, Vector};
+use math::ray::{new_ray, ray_color, ray_color_spectral, Ray};
+use math::vec3::color;
 
+mod load;
 mod save;
 
 mod material;
-use material::{Lambertian, Material};
+mod medium;
+mod microfacet;
+mod texture;
 
 mod shapes;
-use shapes::{sphere, HitRecord};
+use shapes::HitRecord;
 
 mod camera;
-use camera::Camera;
+use camera::{StereoCamera, StereoLayout};
+
+mod scenes;
 
 use save::ppm_header;
 
-use crate::material::{Dielectric, Metal};
 use crate::save::{estimated_time, save_color};
-use crate::shapes::HittableList;
 fn main() -> Result<(), Error> {
     //?Create a new file for image
     let path = "Image.ppm";
@@ -33,96 +36,49 @@ fn main() -> Result<(), Error> {
     let mut file = BufWriter::new(file);
 
     //?Image
-    let aspect_ratio = 3.0 / 2.0;
+    // 0: perspective, 1: orthographic, 2: equirectangular, 3: fisheye, 4: cube map
+    let projection = 0;
+    // Panoramas come out best at their own shapes.
+    let aspect_ratio = match projection {
+        2 => 2.0,
+        3 => 1.0,
+        _ => 3.0 / 2.0,
+    };
     let image_width = 500;
-    let image_height = (image_width as f64 / aspect_ratio) as i32;
+    // 0: mono, 1: side-by-side stereo, 2: over-under stereo
+    let stereo = 0;
     let samples_per_pixel = 50;
     let max_depth = 50;
-
-    //?World
-    let mut world = random_scene();
-
-    pub fn random_scene() -> HittableList {
-        let mut world = HittableList { objects: vec![] };
-        let ground_material = Arc::new(Lambertian {
-            albedo: color(0.5, 0.5, 0.5),
-        });
-        world.add(Arc::new(sphere(
-            point(0.0, -1000.0, 0.0),
-            1000.0,
-            ground_material,
-        )));
-
-        for a in -11..11 {
-            for b in -11..11 {
-                let choose_mat = random_f64(0.0, 1.0);
-                let center = point(
-                    a as f64 + 0.9 * random_f64(0.0, 1.0),
-                    0.2,
-                    b as f64 + 0.9 * random_f64(0.0, 1.0),
-                );
-
-                if (center - point(4.0, 0.2, 0.0)).length() > 0.9 {
-                    let sphere_material: Arc<dyn Material>;
-                    if choose_mat < 0.8 {
-                        // diffuse
-                        let albedo = ColorRGB::random(0.0, 1.0) * ColorRGB::random(0.0, 1.0);
-                        sphere_material = Arc::new(Lambertian { albedo });
-                        world.add(Arc::new(sphere(center, 0.2, sphere_material)));
-                    } else if choose_mat < 0.95 {
-                        // metal
-                        let albedo = ColorRGB::random(0.5, 1.0);
-                        let fuzz = random_f64(0.0, 0.5);
-                        sphere_material = Arc::new(Metal {
-                            albedo,
-                            roughness: fuzz,
-                        });
-                        world.add(Arc::new(sphere(center, 0.2, sphere_material)));
-                    } else {
-                        // glass
-                        sphere_material = Arc::new(Dielectric {
-                            index_of_refraction: 1.5,
-                        });
-                        world.add(Arc::new(sphere(center, 0.2, sphere_material)));
-                    }
-                }
-            }
-        }
-
-        let material1 = Arc::new(Dielectric {
-            index_of_refraction: 1.5,
-        });
-        world.add(Arc::new(sphere(point(0.0, 1.0, 0.0), 1.0, material1)));
-
-        let material2 = Arc::new(Lambertian {
-            albedo: color(0.4, 0.2, 0.1),
-        });
-        world.add(Arc::new(sphere(point(-4.0, 1.0, 0.0), 1.0, material2)));
-
-        let material3 = Arc::new(Metal {
-            albedo: color(0.7, 0.6, 0.5),
-            roughness: 0.0,
-        });
-        world.add(Arc::new(sphere(point(4.0, 1.0, 0.0), 1.0, material3)));
-
-        return world;
+    // Carry radiance as sampled wavelengths instead of RGB (slower, but gets dispersion right)
+    let spectral = false;
+
+    //?World and Camera
+    // 0: random spheres, 1: materials, 2: volumes, 3: shapes
+    let scene = 0;
+    let (world, camera) = match scene {
+        1 => scenes::materials(aspect_ratio)?,
+        2 => scenes::volumes(aspect_ratio)?,
+        3 => scenes::shapes(aspect_ratio, image_width)?,
+        _ => scenes::random_spheres(aspect_ratio),
+    };
+    let camera = match projection {
+        1 => camera.with_orthographic(16.0),
+        2 => camera.with_equirectangular(),
+        3 => camera.with_fisheye(180.0),
+        4 => camera.with_cube_map(),
+        _ => camera,
+    };
+    let stereo_camera = match stereo {
+        1 => Some(StereoLayout::SideBySide),
+        2 => Some(StereoLayout::OverUnder),
+        _ => None,
     }
-
-    //?Camera
-
-    let lookfrom = point(13.0, 2.0, 3.0);
-    let lookat = point(0.0, 0.0, 0.0);
-    // let dist_to_focus = (lookfrom - lookat).length();
-    let dist_to_focus = 10.0;
-    let camera = Camera::new(
-        lookfrom,
-        lookat,
-        vec3(0.0, 1.0, 0.0),
-        20.0,
-        aspect_ratio,
-        0.1,
-        dist_to_focus,
-    );
+    .map(|layout| StereoCamera::new(camera.clone(), 0.064, 10.0, layout));
+    // `aspect_ratio` is each eye's, a stereo frame holds two of them.
+    let frame_aspect = stereo_camera
+        .as_ref()
+        .map_or(aspect_ratio, |stereo| stereo.aspect_ratio());
+    let image_height = (image_width as f64 / frame_aspect) as i32;
 
     //timer
     let before = Instant::now();
@@ -151,8 +107,17 @@ fn main() -> Result<(), Error> {
             for _sample in 0..samples_per_pixel {
                 let u = (i as f64 + random_f64(0.0, 1.0)) / (image_width as f64 - 1.0);
                 let v = (j as f64 + random_f64(0.0, 1.0)) / (image_height as f64 - 1.0);
-                let ray = camera.get_ray(u, v);
-                pixel_color += ray_color(&ray, &mut world, max_depth);
+                let ray = match &stereo_camera {
+                    Some(stereo) => stereo.get_ray(u, v),
+                    None => camera.get_ray(u, v),
+                };
+                if let Some(ray) = ray {
+                    pixel_color += if spectral {
+                        ray_color_spectral(&ray, &world, max_depth)
+                    } else {
+                        ray_color(&ray, &world, max_depth)
+                    };
+                }
             }
             // write_color(pixel_color, samples_per_pixel);
             save_color(&mut file, pixel_color, samples_per_pixel)?;
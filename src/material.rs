@@ -1,13 +1,47 @@
-use crate::math::base::min;
-use crate::math::rand::{random_f64, random_in_unit_sphere, random_unit_vector};
-use crate::math::vec3::{color, dot, unit_vector, ColorRGB, Vec3, Vector};
+use std::sync::Arc;
+
+use crate::math::base::{degrees_to_radians, min};
+use crate::math::constants::PI;
+use crate::math::onb::Onb;
+use crate::math::rand::{random_f64, random_in_unit_sphere, random_normal, random_unit_vector};
+use crate::math::spectrum::{SampledSpectrum, SampledWavelengths, N_SPECTRUM_SAMPLES};
+use crate::math::vec3::{color, cross, dot, lerp, luminance, unit_vector, ColorRGB, Vec3, Vector};
+use crate::medium::{HomogeneousMedium, Medium};
+use crate::microfacet::{
+    fresnel_schlick, ggx_sample_weight, roughness_to_alpha, sample_ggx_half_vector, schlick_weight,
+};
+use crate::texture::{solid_color, Texture};
 
 use crate::{new_ray, Ray};
 
 use super::HitRecord;
 
-pub trait Material {
+pub trait Material: Send + Sync {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(ColorRGB, Ray)>;
+
+    /// Spectral version of `scatter` used by the hero wavelength renderer.
+    /// By default the RGB attenuation is upsampled to the path's wavelengths;
+    /// wavelength dependent materials override this.
+    fn scatter_spectral(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        wavelengths: &mut SampledWavelengths,
+    ) -> Option<(SampledSpectrum, Ray)> {
+        self.scatter(r_in, rec).map(|(attenuation, scattered)| {
+            (
+                SampledSpectrum::from_rgb(attenuation, wavelengths),
+                scattered,
+            )
+        })
+    }
+
+    /// Medium filling objects made of this material. Rays that `scatter`
+    /// sends through the surface into the object random walk through it
+    /// until they leave through the surface again.
+    fn interior(&self) -> Option<&dyn Medium> {
+        None
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -24,12 +58,67 @@ impl Material for Lambertian {
             scatter_direction = rec.normal;
         }
 
-        let scattered = new_ray(&rec.p, &scatter_direction);
+        let scattered = new_ray(&rec.p, &scatter_direction, r_in.time());
         let attenuation = self.albedo;
         Some((attenuation, scattered))
     }
 }
 
+/// Rough diffuse reflector following the Oren-Nayar microfacet model.
+/// `sigma` is the standard deviation of the facet slopes in degrees; zero
+/// reduces to `Lambertian`, larger values flatten the shading and add the
+/// back-scattering seen on clay, concrete and the moon.
+#[derive(Debug, Copy, Clone)]
+pub struct OrenNayar {
+    pub albedo: ColorRGB,
+    pub sigma: f64,
+}
+
+impl Material for OrenNayar {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(ColorRGB, Ray)> {
+        let mut scatter_direction = rec.normal + random_unit_vector();
+
+        if scatter_direction.near_zero() {
+            scatter_direction = rec.normal;
+        }
+
+        let sigma = degrees_to_radians(self.sigma);
+        let sigma2 = sigma * sigma;
+        let a = 1.0 - sigma2 / (2.0 * (sigma2 + 0.33));
+        let b = 0.45 * sigma2 / (sigma2 + 0.09);
+
+        let l = unit_vector(scatter_direction);
+        let v = -unit_vector(r_in.direction());
+        let cos_i = dot(&rec.normal, &l).max(0.0);
+        let cos_o = dot(&rec.normal, &v).max(0.0);
+        let sin_i = (1.0 - cos_i * cos_i).sqrt();
+        let sin_o = (1.0 - cos_o * cos_o).sqrt();
+
+        // Cosine of the azimuthal angle between the two directions.
+        let l_tangent = l - cos_i * rec.normal;
+        let v_tangent = v - cos_o * rec.normal;
+        let max_cos = if l_tangent.near_zero() || v_tangent.near_zero() {
+            0.0
+        } else {
+            dot(&unit_vector(l_tangent), &unit_vector(v_tangent)).max(0.0)
+        };
+
+        // sin(alpha) * tan(beta) with alpha the larger and beta the smaller polar angle.
+        let sin_alpha_tan_beta = if cos_i > cos_o {
+            sin_o * sin_i / cos_i
+        } else if cos_o > 0.0 {
+            sin_i * sin_o / cos_o
+        } else {
+            0.0
+        };
+
+        // Cosine weighted sampling leaves only the albedo scaled by the model's factor.
+        let attenuation = self.albedo * (a + b * max_cos * sin_alpha_tan_beta);
+        let scattered = new_ray(&rec.p, &scatter_direction, r_in.time());
+        Some((attenuation, scattered))
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct Metal {
     pub albedo: ColorRGB,
@@ -42,6 +131,7 @@ impl Material for Metal {
         let scattered = new_ray(
             &rec.p,
             &(reflected + self.roughness * random_in_unit_sphere()),
+            r_in.time(),
         );
         let attenuation = self.albedo;
 
@@ -79,22 +169,773 @@ impl Material for Dielectric {
                 refract(&unit_direction, &rec.normal, refraction_ratio)
             };
 
-        let scattered = new_ray(&rec.p, &direction);
+        let scattered = new_ray(&rec.p, &direction, r_in.time());
         return Some((attenuation, scattered));
     }
 }
 
+/// A dielectric whose index of refraction varies with wavelength, following
+/// Cauchy's equation fitted to the index at the sodium d-line and the Abbe number.
+/// Only the spectral renderer can split light into its colours; the RGB path
+/// treats it like a `Dielectric` with `index_of_refraction`.
+#[derive(Debug, Copy, Clone)]
+pub struct Dispersive {
+    pub index_of_refraction: f64,
+    pub abbe_number: f64,
+}
+
+impl Dispersive {
+    pub fn index_at(&self, lambda: f64) -> f64 {
+        // Fraunhofer d, F and C lines in micrometres.
+        let (lambda_d, lambda_f, lambda_c) = (0.5876, 0.4861, 0.6563);
+        let b = (self.index_of_refraction - 1.0)
+            / (self.abbe_number * (1.0 / (lambda_f * lambda_f) - 1.0 / (lambda_c * lambda_c)));
+        let a = self.index_of_refraction - b / (lambda_d * lambda_d);
+        let micrometres = lambda / 1000.0;
+        a + b / (micrometres * micrometres)
+    }
+}
+
+impl Material for Dispersive {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(ColorRGB, Ray)> {
+        Dielectric {
+            index_of_refraction: self.index_of_refraction,
+        }
+        .scatter(r_in, rec)
+    }
+
+    fn scatter_spectral(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        wavelengths: &mut SampledWavelengths,
+    ) -> Option<(SampledSpectrum, Ray)> {
+        // Each wavelength would refract in its own direction, so only the hero survives.
+        wavelengths.terminate_secondary();
+        let dielectric = Dielectric {
+            index_of_refraction: self.index_at(wavelengths.hero()),
+        };
+        dielectric
+            .scatter(r_in, rec)
+            .map(|(_, scattered)| (SampledSpectrum::constant(1.0), scattered))
+    }
+}
+
+/// Disney style "principled" uber material. Every parameter is in [0, 1]
+/// except `index_of_refraction`; start from `principled` and override fields.
+///
+/// The BSDF is a weighted sum of a diffuse lobe (with sheen and a subsurface
+/// approximation), a GGX specular lobe, a rough dielectric transmission lobe
+/// and a GGX clear coat. One lobe is picked per scatter event in proportion to
+/// its weight and its sample is divided by the probability of picking it.
+#[derive(Debug, Copy, Clone)]
+pub struct Principled {
+    pub base_color: ColorRGB,
+    pub metallic: f64,
+    pub roughness: f64,
+    pub specular: f64,
+    pub specular_tint: f64,
+    pub sheen: f64,
+    pub sheen_tint: f64,
+    pub clearcoat: f64,
+    pub clearcoat_gloss: f64,
+    pub transmission: f64,
+    pub subsurface: f64,
+    pub index_of_refraction: f64,
+}
+
+pub fn principled(base_color: ColorRGB) -> Principled {
+    Principled {
+        base_color,
+        metallic: 0.0,
+        roughness: 0.5,
+        specular: 0.5,
+        specular_tint: 0.0,
+        sheen: 0.0,
+        sheen_tint: 0.5,
+        clearcoat: 0.0,
+        clearcoat_gloss: 1.0,
+        transmission: 0.0,
+        subsurface: 0.0,
+        index_of_refraction: 1.5,
+    }
+}
+
+impl Principled {
+    /// Base color normalised to unit luminance, used by the tint parameters.
+    fn tint(&self) -> ColorRGB {
+        let lum = luminance(self.base_color);
+        if lum > 0.0 {
+            self.base_color / lum
+        } else {
+            color(1.0, 1.0, 1.0)
+        }
+    }
+
+    fn specular_f0(&self) -> ColorRGB {
+        let dielectric_f0 =
+            0.08 * self.specular * lerp(color(1.0, 1.0, 1.0), self.tint(), self.specular_tint);
+        lerp(dielectric_f0, self.base_color, self.metallic)
+    }
+
+    fn sample_diffuse(&self, v: &Vec3, normal: &Vec3) -> Option<(ColorRGB, Vec3)> {
+        let mut direction = normal + random_unit_vector();
+        if direction.near_zero() {
+            direction = *normal;
+        }
+        let l = unit_vector(direction);
+        let n_dot_l = dot(normal, &l);
+        let n_dot_v = dot(normal, v);
+        if n_dot_l <= 0.0 {
+            return None;
+        }
+        let l_dot_h = dot(&l, &unit_vector(l + v));
+
+        let fl = schlick_weight(n_dot_l);
+        let fv = schlick_weight(n_dot_v);
+        let fd90 = 0.5 + 2.0 * self.roughness * l_dot_h * l_dot_h;
+        let fd = (1.0 + (fd90 - 1.0) * fl) * (1.0 + (fd90 - 1.0) * fv);
+
+        // Hanrahan-Krueger inspired flattening used to fake subsurface scattering.
+        let fss90 = self.roughness * l_dot_h * l_dot_h;
+        let fss = (1.0 + (fss90 - 1.0) * fl) * (1.0 + (fss90 - 1.0) * fv);
+        let ss = 1.25 * (fss * (1.0 / (n_dot_l + n_dot_v) - 0.5) + 0.5);
+
+        let sheen_color = lerp(color(1.0, 1.0, 1.0), self.tint(), self.sheen_tint);
+        let sheen = self.sheen * schlick_weight(l_dot_h) * sheen_color;
+
+        // Cosine weighted sampling cancels the cosine and the 1 / pi of the diffuse term.
+        let diffuse = self.base_color * (fd + (ss - fd) * self.subsurface);
+        Some((diffuse + PI * sheen, l))
+    }
+
+    fn sample_glossy(
+        v: &Vec3,
+        normal: &Vec3,
+        alpha: f64,
+        f0: ColorRGB,
+    ) -> Option<(ColorRGB, Vec3)> {
+        let h = sample_ggx_half_vector(normal, alpha);
+        let l = reflect(&-v, &h);
+        let n_dot_l = dot(normal, &l);
+        if n_dot_l <= 0.0 {
+            return None;
+        }
+        let v_dot_h = dot(v, &h);
+        let weight = ggx_sample_weight(n_dot_l, dot(normal, v), dot(normal, &h), v_dot_h, alpha);
+        Some((fresnel_schlick(v_dot_h, f0) * weight, l))
+    }
+
+    fn sample_transmission(&self, v: &Vec3, rec: &HitRecord) -> Option<(ColorRGB, Vec3)> {
+        let alpha = roughness_to_alpha(self.roughness);
+        let refraction_ratio = if rec.front_face {
+            1.0 / self.index_of_refraction
+        } else {
+            self.index_of_refraction
+        };
+
+        let h = sample_ggx_half_vector(&rec.normal, alpha);
+        let v_dot_h = dot(v, &h);
+        if v_dot_h <= 0.0 {
+            return None;
+        }
+        let n_dot_v = dot(&rec.normal, v);
+        let n_dot_h = dot(&rec.normal, &h);
+        let sin_theta = (1.0 - v_dot_h * v_dot_h).sqrt();
+
+        // Reflect or refract about the microfacet with probability equal to the
+        // Fresnel term, which then cancels out of the weight.
+        let cannot_refract = refraction_ratio * sin_theta > 1.0;
+        if cannot_refract || reflectance(v_dot_h, refraction_ratio) > random_f64(0.0, 1.0) {
+            let l = reflect(&-v, &h);
+            let n_dot_l = dot(&rec.normal, &l);
+            if n_dot_l <= 0.0 {
+                return None;
+            }
+            let weight = ggx_sample_weight(n_dot_l, n_dot_v, n_dot_h, v_dot_h, alpha);
+            Some((color(weight, weight, weight), l))
+        } else {
+            let l = refract(&-v, &h, refraction_ratio);
+            let n_dot_l = dot(&rec.normal, &l);
+            if n_dot_l >= 0.0 {
+                return None;
+            }
+            let weight = ggx_sample_weight(n_dot_l, n_dot_v, n_dot_h, v_dot_h, alpha);
+            Some((self.base_color * weight, l))
+        }
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(ColorRGB, Ray)> {
+        let v = -unit_vector(r_in.direction());
+        let n_dot_v = dot(&rec.normal, &v);
+        if n_dot_v <= 0.0 {
+            return None;
+        }
+
+        let f0 = self.specular_f0();
+        let transmission_weight = (1.0 - self.metallic) * self.transmission;
+        let lobe_weights = [
+            (1.0 - self.metallic) * (1.0 - self.transmission),
+            1.0 - transmission_weight,
+            transmission_weight,
+            0.25 * self.clearcoat,
+        ];
+        // Selection probabilities favour lobes that reflect more light; the
+        // specular lobe is never starved so highlights on plastics converge.
+        let lobe_importance = [
+            lobe_weights[0],
+            lobe_weights[1] * luminance(fresnel_schlick(n_dot_v, f0)).max(0.1),
+            lobe_weights[2],
+            lobe_weights[3],
+        ];
+        let total: f64 = lobe_importance.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut pick = random_f64(0.0, total);
+        let mut lobe = 0;
+        while lobe < lobe_importance.len() - 1 && pick >= lobe_importance[lobe] {
+            pick -= lobe_importance[lobe];
+            lobe += 1;
+        }
+
+        let sample = match lobe {
+            0 => self.sample_diffuse(&v, &rec.normal),
+            1 => Principled::sample_glossy(&v, &rec.normal, roughness_to_alpha(self.roughness), f0),
+            2 => self.sample_transmission(&v, rec),
+            _ => {
+                let alpha = 0.1 + (0.001 - 0.1) * self.clearcoat_gloss;
+                Principled::sample_glossy(&v, &rec.normal, alpha, color(0.04, 0.04, 0.04))
+            }
+        };
+
+        sample.map(|(weight, direction)| {
+            let probability = lobe_importance[lobe] / total;
+            let attenuation = weight * (lobe_weights[lobe] / probability);
+            (attenuation, new_ray(&rec.p, &direction, r_in.time()))
+        })
+    }
+}
+
+/// A thin dielectric coat (car paint, varnish, glossy plastic) layered over any
+/// other material. Light either reflects off the coat with the Fresnel
+/// probability or passes through it to `base`, picking up the coat's
+/// absorption on the way in and out. Light bouncing around inside the coat
+/// more than once is ignored.
+pub struct Coated {
+    pub base: Arc<dyn Material>,
+    pub index_of_refraction: f64,
+    pub roughness: f64,
+    /// Absorption coefficient per unit `thickness`; black for a clear coat.
+    pub absorption: ColorRGB,
+    pub thickness: f64,
+}
+
+enum CoatEvent {
+    Reflected(f64, Vec3),
+    Transmitted,
+    Absorbed,
+}
+
+impl Coated {
+    /// Samples the coat interface, either reflecting off it or letting the
+    /// light through to the base.
+    fn sample_coat(&self, v: &Vec3, normal: &Vec3) -> CoatEvent {
+        let alpha = roughness_to_alpha(self.roughness);
+        let h = sample_ggx_half_vector(normal, alpha);
+        let v_dot_h = dot(v, &h);
+        if v_dot_h <= 0.0
+            || reflectance(v_dot_h, 1.0 / self.index_of_refraction) <= random_f64(0.0, 1.0)
+        {
+            return CoatEvent::Transmitted;
+        }
+
+        let l = reflect(&-v, &h);
+        let n_dot_l = dot(normal, &l);
+        if n_dot_l <= 0.0 {
+            return CoatEvent::Absorbed;
+        }
+        let weight = ggx_sample_weight(n_dot_l, dot(normal, v), dot(normal, &h), v_dot_h, alpha);
+        CoatEvent::Reflected(weight, l)
+    }
+
+    /// Attenuation picked up by light that went through the coat to the base
+    /// and back out along `scattered`.
+    fn transmittance(&self, v: &Vec3, scattered: &Ray, normal: &Vec3) -> ColorRGB {
+        let cos_out = dot(&unit_vector(scattered.direction()), normal);
+        if cos_out <= 0.0 {
+            return color(0.0, 0.0, 0.0);
+        }
+        let exit = 1.0 - reflectance(cos_out, 1.0 / self.index_of_refraction);
+
+        // Path length through the coat follows the refracted angles.
+        let refracted_cos = |cosine: f64| {
+            let sin2 =
+                (1.0 - cosine * cosine) / (self.index_of_refraction * self.index_of_refraction);
+            (1.0 - sin2).sqrt()
+        };
+        let path =
+            self.thickness * (1.0 / refracted_cos(dot(v, normal)) + 1.0 / refracted_cos(cos_out));
+        let absorbed = -path * self.absorption;
+        exit * color(absorbed.x().exp(), absorbed.y().exp(), absorbed.z().exp())
+    }
+}
+
+impl Material for Coated {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(ColorRGB, Ray)> {
+        if !rec.front_face {
+            return self.base.scatter(r_in, rec);
+        }
+
+        let v = -unit_vector(r_in.direction());
+        match self.sample_coat(&v, &rec.normal) {
+            CoatEvent::Reflected(weight, direction) => Some((
+                color(weight, weight, weight),
+                new_ray(&rec.p, &direction, r_in.time()),
+            )),
+            CoatEvent::Absorbed => None,
+            CoatEvent::Transmitted => {
+                self.base
+                    .scatter(r_in, rec)
+                    .map(|(attenuation, scattered)| {
+                        (
+                            attenuation * self.transmittance(&v, &scattered, &rec.normal),
+                            scattered,
+                        )
+                    })
+            }
+        }
+    }
+
+    fn scatter_spectral(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        wavelengths: &mut SampledWavelengths,
+    ) -> Option<(SampledSpectrum, Ray)> {
+        if !rec.front_face {
+            return self.base.scatter_spectral(r_in, rec, wavelengths);
+        }
+
+        let v = -unit_vector(r_in.direction());
+        match self.sample_coat(&v, &rec.normal) {
+            CoatEvent::Reflected(weight, direction) => Some((
+                SampledSpectrum::constant(weight),
+                new_ray(&rec.p, &direction, r_in.time()),
+            )),
+            CoatEvent::Absorbed => None,
+            CoatEvent::Transmitted => {
+                let (attenuation, scattered) =
+                    self.base.scatter_spectral(r_in, rec, wavelengths)?;
+                let coat = self.transmittance(&v, &scattered, &rec.normal);
+                Some((
+                    attenuation * SampledSpectrum::from_rgb(coat, wavelengths),
+                    scattered,
+                ))
+            }
+        }
+    }
+}
+
+/// Picks between two materials per scatter event, choosing `second` with a
+/// probability given by the luminance of `weight` at the hit's UV. On average
+/// this is the same as blending the two materials' results.
+pub struct Mix {
+    pub first: Arc<dyn Material>,
+    pub second: Arc<dyn Material>,
+    pub weight: Arc<dyn Texture>,
+}
+
+/// Mixes two materials with a constant weight.
+pub fn mix(first: Arc<dyn Material>, second: Arc<dyn Material>, weight: f64) -> Mix {
+    Mix {
+        first,
+        second,
+        weight: solid_color(color(weight, weight, weight)),
+    }
+}
+
+impl Mix {
+    fn pick(&self, rec: &HitRecord) -> &Arc<dyn Material> {
+        let weight = luminance(self.weight.value(rec.u, rec.v, &rec.p));
+        if random_f64(0.0, 1.0) < weight {
+            &self.second
+        } else {
+            &self.first
+        }
+    }
+}
+
+impl Material for Mix {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(ColorRGB, Ray)> {
+        self.pick(rec).scatter(r_in, rec)
+    }
+
+    fn scatter_spectral(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        wavelengths: &mut SampledWavelengths,
+    ) -> Option<(SampledSpectrum, Ray)> {
+        self.pick(rec).scatter_spectral(r_in, rec, wavelengths)
+    }
+}
+
+/// Alpha mask for cut-out geometry such as leaves, fences and decals. Where
+/// the luminance of `opacity` is zero rays carry on through the surface
+/// untouched; partial opacity lets the matching fraction of rays through.
+pub struct Cutout {
+    pub material: Arc<dyn Material>,
+    pub opacity: Arc<dyn Texture>,
+}
+
+impl Cutout {
+    fn passes_through(&self, rec: &HitRecord) -> bool {
+        let opacity = luminance(self.opacity.value(rec.u, rec.v, &rec.p));
+        opacity <= 0.0 || random_f64(0.0, 1.0) >= opacity
+    }
+}
+
+impl Material for Cutout {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(ColorRGB, Ray)> {
+        if self.passes_through(rec) {
+            return Some((
+                color(1.0, 1.0, 1.0),
+                new_ray(&rec.p, &r_in.direction(), r_in.time()),
+            ));
+        }
+        self.material.scatter(r_in, rec)
+    }
+
+    fn scatter_spectral(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        wavelengths: &mut SampledWavelengths,
+    ) -> Option<(SampledSpectrum, Ray)> {
+        if self.passes_through(rec) {
+            return Some((
+                SampledSpectrum::constant(1.0),
+                new_ray(&rec.p, &r_in.direction(), r_in.time()),
+            ));
+        }
+        self.material.scatter_spectral(r_in, rec, wavelengths)
+    }
+}
+
+/// Subsurface scattering for skin, wax, marble and milk: a smooth dielectric
+/// boundary around a homogeneous scattering medium. Light refracted inside
+/// random walks through `medium` until it finds its way back out.
+#[derive(Debug, Copy, Clone)]
+pub struct Subsurface {
+    pub index_of_refraction: f64,
+    pub medium: HomogeneousMedium,
+}
+
+impl Material for Subsurface {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(ColorRGB, Ray)> {
+        Dielectric {
+            index_of_refraction: self.index_of_refraction,
+        }
+        .scatter(r_in, rec)
+    }
+
+    fn interior(&self) -> Option<&dyn Medium> {
+        Some(&self.medium)
+    }
+}
+
+/// Invisible surface of a participating medium; see `ConstantMedium`.
+pub struct MediumBoundary {
+    pub medium: Arc<dyn Medium>,
+}
+
+impl Material for MediumBoundary {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(ColorRGB, Ray)> {
+        Some((
+            color(1.0, 1.0, 1.0),
+            new_ray(&rec.p, &r_in.direction(), r_in.time()),
+        ))
+    }
+
+    fn interior(&self) -> Option<&dyn Medium> {
+        Some(self.medium.as_ref())
+    }
+}
+
+/// Thin film (soap bubbles, oil slicks) over any other material. Interference
+/// inside the film makes its reflectance depend on wavelength; the RGB
+/// renderer evaluates it at one representative wavelength per channel while
+/// the spectral renderer evaluates it at every sampled wavelength. Light
+/// either reflects off the film or passes through it to `base`.
+///
+/// The film's reflectance is worked out against a base of index
+/// `index_of_refraction`. Like `Coated`, the base still reflects as it
+/// normally would, so a film over glass reflects a little more than it should.
+/// A free-standing film such as a bubble has an index of 1 and a `Dielectric`
+/// of index 1 as its base, which lets the light straight through.
+pub struct ThinFilm {
+    pub base: Arc<dyn Material>,
+    /// Film thickness in nanometres, read from the texture's luminance.
+    pub thickness: Arc<dyn Texture>,
+    pub film_index: f64,
+    pub index_of_refraction: f64,
+}
+
+pub fn thin_film(
+    base: Arc<dyn Material>,
+    thickness: f64,
+    film_index: f64,
+    index_of_refraction: f64,
+) -> ThinFilm {
+    ThinFilm {
+        base,
+        thickness: solid_color(color(thickness, thickness, thickness)),
+        film_index,
+        index_of_refraction,
+    }
+}
+
+/// Wavelengths in nanometres standing in for the red, green and blue channels.
+const RGB_WAVELENGTHS: [f64; 3] = [630.0, 532.0, 465.0];
+
+impl ThinFilm {
+    /// Indices on the incident and transmitted side of the film.
+    fn indices(&self, rec: &HitRecord) -> (f64, f64) {
+        if rec.front_face {
+            (1.0, self.index_of_refraction)
+        } else {
+            (self.index_of_refraction, 1.0)
+        }
+    }
+
+    fn reflectance_at(&self, cosine: f64, rec: &HitRecord, lambda: f64) -> f64 {
+        let (n_incident, n_transmitted) = self.indices(rec);
+        let thickness = luminance(self.thickness.value(rec.u, rec.v, &rec.p));
+        thin_film_reflectance(
+            cosine,
+            n_incident,
+            self.film_index,
+            n_transmitted,
+            thickness,
+            lambda,
+        )
+    }
+
+    fn reflected(&self, r_in: &Ray, rec: &HitRecord) -> Ray {
+        let direction = reflect(&unit_vector(r_in.direction()), &rec.normal);
+        new_ray(&rec.p, &direction, r_in.time())
+    }
+}
+
+/// Weight that turns the channel-averaged choice into per channel reflectance.
+fn thin_film_weight(reflected: bool, reflectance: f64, average: f64) -> f64 {
+    if reflected {
+        reflectance / average
+    } else {
+        (1.0 - reflectance) / (1.0 - average)
+    }
+}
+
+// Both renderers reflect off the film with the reflectance averaged over
+// their channels and hand the light to the base otherwise.
+impl Material for ThinFilm {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(ColorRGB, Ray)> {
+        let cosine = min(dot(&-unit_vector(r_in.direction()), &rec.normal), 1.0);
+        let r = RGB_WAVELENGTHS.map(|lambda| self.reflectance_at(cosine, rec, lambda));
+        let average = (r[0] + r[1] + r[2]) / 3.0;
+
+        let reflected = average > random_f64(0.0, 1.0);
+        let weight = color(
+            thin_film_weight(reflected, r[0], average),
+            thin_film_weight(reflected, r[1], average),
+            thin_film_weight(reflected, r[2], average),
+        );
+        if reflected {
+            return Some((weight, self.reflected(r_in, rec)));
+        }
+        let (attenuation, scattered) = self.base.scatter(r_in, rec)?;
+        Some((weight * attenuation, scattered))
+    }
+
+    fn scatter_spectral(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        wavelengths: &mut SampledWavelengths,
+    ) -> Option<(SampledSpectrum, Ray)> {
+        let cosine = min(dot(&-unit_vector(r_in.direction()), &rec.normal), 1.0);
+        let mut r = SampledSpectrum::constant(0.0);
+        for i in 0..N_SPECTRUM_SAMPLES {
+            r.set_value(i, self.reflectance_at(cosine, rec, wavelengths.lambda(i)));
+        }
+        let average = r.average();
+
+        let reflected = average > random_f64(0.0, 1.0);
+        let mut weight = SampledSpectrum::constant(0.0);
+        for i in 0..N_SPECTRUM_SAMPLES {
+            weight.set_value(i, thin_film_weight(reflected, r.value(i), average));
+        }
+        if reflected {
+            return Some((weight, self.reflected(r_in, rec)));
+        }
+        let (attenuation, scattered) = self.base.scatter_spectral(r_in, rec, wavelengths)?;
+        Some((weight * attenuation, scattered))
+    }
+}
+
+/// Simplified Marschner hair model. Light reflects off the fibre's surface
+/// (R), passes through it (TT) or reflects once inside it (TRT), and one of
+/// these is picked per scatter event in proportion to its weight. Each leaves
+/// at the mirrored angle along the fibre, shifted by the tilt of the cuticle
+/// scales and blurred by `roughness`. Around the fibre the exit direction
+/// follows from where across it the light struck, which is sampled uniformly.
+///
+/// Uses the hit's tangent as the fibre direction, so it is meant for curves.
+#[derive(Debug, Copy, Clone)]
+pub struct Hair {
+    /// Colour picked up by light passing once through the middle of the fibre.
+    pub color: ColorRGB,
+    /// Spread of the lobes along the fibre, in radians.
+    pub roughness: f64,
+    /// Tilt of the cuticle scales, in radians.
+    pub cuticle_tilt: f64,
+    pub index_of_refraction: f64,
+}
+
+pub fn hair(color: ColorRGB) -> Hair {
+    Hair {
+        color,
+        roughness: 0.15,
+        cuticle_tilt: degrees_to_radians(2.0),
+        index_of_refraction: 1.55,
+    }
+}
+
+impl Material for Hair {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(ColorRGB, Ray)> {
+        // Without a tangent, pretend the fibre runs across the surface.
+        let tangent = rec
+            .tangent
+            .unwrap_or_else(|| Onb::build_from_w(&rec.normal).u);
+        let wo = -unit_vector(r_in.direction());
+        let sin_theta_o = dot(&wo, &tangent).clamp(-1.0, 1.0);
+        let cos_theta_o = (1.0 - sin_theta_o * sin_theta_o).max(1e-6).sqrt();
+
+        // Azimuth is measured around the fibre from the side facing the viewer.
+        let across = wo - sin_theta_o * tangent;
+        let toward_viewer = if across.length_squared() > 1e-12 {
+            unit_vector(across)
+        } else {
+            unit_vector(rec.normal - dot(&rec.normal, &tangent) * tangent)
+        };
+        let side = cross(&tangent, &toward_viewer);
+
+        // Where across the fibre the light strikes, and the angles it makes
+        // with the fibre's cross section outside and inside.
+        let h = random_f64(-1.0, 1.0);
+        let gamma_o = h.asin();
+        let eta = self.index_of_refraction;
+        let sin_theta_t = sin_theta_o / eta;
+        let cos_theta_t = (1.0 - sin_theta_t * sin_theta_t).sqrt();
+        let eta_projected = (eta * eta - sin_theta_o * sin_theta_o).sqrt() / cos_theta_o;
+        let gamma_t = (h / eta_projected).clamp(-1.0, 1.0).asin();
+
+        let f = reflectance(cos_theta_o * gamma_o.cos(), eta);
+        // One pass through the fibre, scaled from the pass through the middle.
+        let pass_length = gamma_t.cos() / cos_theta_t;
+        let pass = color(
+            self.color.x().powf(pass_length),
+            self.color.y().powf(pass_length),
+            self.color.z().powf(pass_length),
+        );
+        let lobe_weights = [
+            color(f, f, f),
+            (1.0 - f) * (1.0 - f) * pass,
+            (1.0 - f) * (1.0 - f) * f * pass * pass,
+        ];
+
+        let importance = lobe_weights.map(luminance);
+        let total: f64 = importance.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut pick = random_f64(0.0, total);
+        let mut lobe = 0;
+        while lobe < 2 && pick >= importance[lobe] {
+            pick -= importance[lobe];
+            lobe += 1;
+        }
+        let attenuation = lobe_weights[lobe] * (total / importance[lobe]);
+
+        let alpha = self.cuticle_tilt;
+        let (shift, spread) = match lobe {
+            0 => (-2.0 * alpha, self.roughness),
+            1 => (alpha, 0.5 * self.roughness),
+            _ => (4.0 * alpha, 2.0 * self.roughness),
+        };
+        let theta_i = (-sin_theta_o.asin() + shift + spread * random_normal())
+            .clamp(-0.5 * PI + 1e-4, 0.5 * PI - 1e-4);
+        let p = lobe as f64;
+        let phi = 2.0 * p * gamma_t - 2.0 * gamma_o + p * PI;
+
+        let direction = theta_i.sin() * tangent
+            + theta_i.cos() * (phi.cos() * toward_viewer + phi.sin() * side);
+        Some((attenuation, new_ray(&rec.p, &direction, r_in.time())))
+    }
+}
+
 pub fn reflect(v: &Vec3, n: &Vec3) -> Vec3 {
-    return v - 2.0 * dot(&v, &n) * n;
+    return v - 2.0 * dot(v, n) * n;
 }
 
 pub fn refract(uv: &Vec3, n: &Vec3, etai_over_etat: f64) -> Vec3 {
-    let cos_theta = min(dot(&-uv, &n), 1.0);
+    let cos_theta = min(dot(&-uv, n), 1.0);
     let r_out_perp = etai_over_etat * (uv + (cos_theta * n));
     let r_out_parallel = -(((1.0 - r_out_perp.length_squared()).abs()).sqrt()) * n;
     return r_out_perp + r_out_parallel;
 }
 
+/// Reflectance of a film of index `n_film` and `thickness` nanometres between
+/// two media, from the Airy summation of the multiple reflections inside the
+/// film, averaged over both polarisations.
+pub fn thin_film_reflectance(
+    cosine: f64,
+    n_incident: f64,
+    n_film: f64,
+    n_transmitted: f64,
+    thickness: f64,
+    lambda: f64,
+) -> f64 {
+    let sin2_incident = 1.0 - cosine * cosine;
+    let sin2_film = sin2_incident * (n_incident / n_film).powi(2);
+    let sin2_transmitted = sin2_incident * (n_incident / n_transmitted).powi(2);
+    if sin2_film >= 1.0 || sin2_transmitted >= 1.0 {
+        return 1.0;
+    }
+    let cos_film = (1.0 - sin2_film).sqrt();
+    let cos_transmitted = (1.0 - sin2_transmitted).sqrt();
+
+    let r12_s =
+        (n_incident * cosine - n_film * cos_film) / (n_incident * cosine + n_film * cos_film);
+    let r12_p =
+        (n_film * cosine - n_incident * cos_film) / (n_film * cosine + n_incident * cos_film);
+    let r23_s = (n_film * cos_film - n_transmitted * cos_transmitted)
+        / (n_film * cos_film + n_transmitted * cos_transmitted);
+    let r23_p = (n_transmitted * cos_film - n_film * cos_transmitted)
+        / (n_transmitted * cos_film + n_film * cos_transmitted);
+
+    // Phase difference between successive reflections inside the film.
+    let cos_delta = (4.0 * PI * n_film * thickness * cos_film / lambda).cos();
+    let airy = |a: f64, b: f64| {
+        (a * a + b * b + 2.0 * a * b * cos_delta) / (1.0 + a * a * b * b + 2.0 * a * b * cos_delta)
+    };
+    0.5 * (airy(r12_s, r23_s) + airy(r12_p, r23_p))
+}
+
 pub fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
     let mut r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
     r0 = r0 * r0;
@@ -1,2 +1,2 @@
 pub const INFINITY: f64 = f64::INFINITY;
-pub const PI: f64 = 3.1415926535897932385;
+pub const PI: f64 = std::f64::consts::PI;
@@ -0,0 +1,185 @@
+use std::ops::Mul;
+
+use super::vec3::{vec3, Point3, Vec3, Vector};
+
+/// 4x4 matrix for affine transforms, stored row-major and applied to column
+/// vectors, so `a * b` applies `b` first.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Mat4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Mat4 {
+    pub fn identity() -> Self {
+        Mat4 {
+            m: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn translation(offset: &Vec3) -> Self {
+        let mut t = Mat4::identity();
+        t.m[0][3] = offset.x();
+        t.m[1][3] = offset.y();
+        t.m[2][3] = offset.z();
+        t
+    }
+
+    pub fn scaling(factors: &Vec3) -> Self {
+        let mut s = Mat4::identity();
+        s.m[0][0] = factors.x();
+        s.m[1][1] = factors.y();
+        s.m[2][2] = factors.z();
+        s
+    }
+
+    /// Counter-clockwise rotation by `radians` about the unit vector `axis`.
+    pub fn rotation(axis: &Vec3, radians: f64) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        let (x, y, z) = (axis.x(), axis.y(), axis.z());
+        let c = 1.0 - cos;
+        Mat4 {
+            m: [
+                [
+                    cos + x * x * c,
+                    x * y * c - z * sin,
+                    x * z * c + y * sin,
+                    0.0,
+                ],
+                [
+                    y * x * c + z * sin,
+                    cos + y * y * c,
+                    y * z * c - x * sin,
+                    0.0,
+                ],
+                [
+                    z * x * c - y * sin,
+                    z * y * c + x * sin,
+                    cos + z * z * c,
+                    0.0,
+                ],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn rotation_x(radians: f64) -> Self {
+        Mat4::rotation(&vec3(1.0, 0.0, 0.0), radians)
+    }
+
+    pub fn rotation_y(radians: f64) -> Self {
+        Mat4::rotation(&vec3(0.0, 1.0, 0.0), radians)
+    }
+
+    pub fn rotation_z(radians: f64) -> Self {
+        Mat4::rotation(&vec3(0.0, 0.0, 1.0), radians)
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut t = Mat4 { m: [[0.0; 4]; 4] };
+        for (i, row) in t.m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        t
+    }
+
+    /// General inverse by Gauss-Jordan elimination with partial pivoting.
+    /// Returns `None` for singular matrices such as a zero scale.
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Mat4::identity().m;
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap_or(col);
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let factor = a[row][col];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[col][j];
+                    inv[row][j] -= factor * inv[col][j];
+                }
+            }
+        }
+        Some(Mat4 { m: inv })
+    }
+
+    pub fn transform_point(&self, p: &Point3) -> Point3 {
+        let m = &self.m;
+        vec3(
+            m[0][0] * p.x() + m[0][1] * p.y() + m[0][2] * p.z() + m[0][3],
+            m[1][0] * p.x() + m[1][1] * p.y() + m[1][2] * p.z() + m[1][3],
+            m[2][0] * p.x() + m[2][1] * p.y() + m[2][2] * p.z() + m[2][3],
+        )
+    }
+
+    pub fn transform_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.m;
+        vec3(
+            m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+            m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+            m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+        )
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, other: Mat4) -> Mat4 {
+        let mut product = Mat4 { m: [[0.0; 4]; 4] };
+        for i in 0..4 {
+            for j in 0..4 {
+                product.m[i][j] = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        product
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: &Mat4, b: &Mat4) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((a.m[i][j] - b.m[i][j]).abs() < 1e-9, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_the_transform() {
+        let m = Mat4::translation(&vec3(1.0, -2.0, 3.0))
+            * Mat4::rotation(&vec3(0.0, 0.6, 0.8), 1.1)
+            * Mat4::scaling(&vec3(2.0, 0.5, 3.0));
+        let inverse = m.inverse().unwrap();
+        assert_near(&(m * inverse), &Mat4::identity());
+        assert_near(&(inverse * m), &Mat4::identity());
+    }
+
+    #[test]
+    fn singular_matrix_has_no_inverse() {
+        assert!(Mat4::scaling(&vec3(1.0, 0.0, 1.0)).inverse().is_none());
+    }
+}
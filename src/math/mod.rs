@@ -9,3 +9,13 @@ pub mod constants;
 pub mod base;
 
 pub mod ray;
+
+pub mod spectrum;
+
+pub mod onb;
+
+pub mod matrix;
+
+pub mod quaternion;
+
+pub mod roots;
@@ -0,0 +1,28 @@
+use super::vec3::{cross, unit_vector, vec3, Vec3, Vector};
+
+/// Orthonormal basis, used to take directions sampled around the z axis
+/// and place them around an arbitrary normal.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn build_from_w(n: &Vec3) -> Self {
+        let w = unit_vector(*n);
+        let a = if w.x().abs() > 0.9 {
+            vec3(0.0, 1.0, 0.0)
+        } else {
+            vec3(1.0, 0.0, 0.0)
+        };
+        let v = unit_vector(cross(&w, &a));
+        let u = cross(&w, &v);
+        Onb { u, v, w }
+    }
+
+    pub fn local(&self, a: &Vec3) -> Vec3 {
+        a.x() * self.u + a.y() * self.v + a.z() * self.w
+    }
+}
@@ -0,0 +1,246 @@
+use std::ops::Mul;
+
+use super::matrix::Mat4;
+use super::vec3::{cross, unit_vector, vec3, Vec3, Vector};
+
+/// Unit quaternion representing a rotation, `w + xi + yj + zk`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub fn identity() -> Self {
+        Quaternion {
+            w: 1.0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+
+    /// Counter-clockwise rotation by `radians` about `axis`.
+    pub fn from_axis_angle(axis: &Vec3, radians: f64) -> Self {
+        let axis = unit_vector(*axis);
+        let (sin, cos) = (0.5 * radians).sin_cos();
+        Quaternion {
+            w: cos,
+            x: axis.x() * sin,
+            y: axis.y() * sin,
+            z: axis.z() * sin,
+        }
+    }
+
+    /// Rotation by `x` radians about the X axis, then `y` about Y, then `z`
+    /// about Z, all about the fixed world axes.
+    pub fn from_euler(x: f64, y: f64, z: f64) -> Self {
+        Quaternion::from_axis_angle(&vec3(0.0, 0.0, 1.0), z)
+            * Quaternion::from_axis_angle(&vec3(0.0, 1.0, 0.0), y)
+            * Quaternion::from_axis_angle(&vec3(1.0, 0.0, 0.0), x)
+    }
+
+    /// Rotation that turns -Z towards `forward` and keeps +Y as close to `up`
+    /// as possible, matching the way `Camera` looks down its -w axis.
+    pub fn look_rotation(forward: &Vec3, up: &Vec3) -> Self {
+        let back = -unit_vector(*forward);
+        let right = unit_vector(cross(up, &back));
+        let true_up = cross(&back, &right);
+
+        // Rotation matrix with the new axes as columns, converted with Shepperd's method.
+        let m = [
+            [right.x(), true_up.x(), back.x()],
+            [right.y(), true_up.y(), back.y()],
+            [right.z(), true_up.z(), back.z()],
+        ];
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let q = if trace > 0.0 {
+            let s = 2.0 * (trace + 1.0).sqrt();
+            Quaternion {
+                w: 0.25 * s,
+                x: (m[2][1] - m[1][2]) / s,
+                y: (m[0][2] - m[2][0]) / s,
+                z: (m[1][0] - m[0][1]) / s,
+            }
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = 2.0 * (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt();
+            Quaternion {
+                w: (m[2][1] - m[1][2]) / s,
+                x: 0.25 * s,
+                y: (m[0][1] + m[1][0]) / s,
+                z: (m[0][2] + m[2][0]) / s,
+            }
+        } else if m[1][1] > m[2][2] {
+            let s = 2.0 * (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt();
+            Quaternion {
+                w: (m[0][2] - m[2][0]) / s,
+                x: (m[0][1] + m[1][0]) / s,
+                y: 0.25 * s,
+                z: (m[1][2] + m[2][1]) / s,
+            }
+        } else {
+            let s = 2.0 * (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt();
+            Quaternion {
+                w: (m[1][0] - m[0][1]) / s,
+                x: (m[0][2] + m[2][0]) / s,
+                y: (m[1][2] + m[2][1]) / s,
+                z: 0.25 * s,
+            }
+        };
+        q.normalized()
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn normalized(&self) -> Self {
+        let length = self.dot(self).sqrt();
+        Quaternion {
+            w: self.w / length,
+            x: self.x / length,
+            y: self.y / length,
+            z: self.z / length,
+        }
+    }
+
+    pub fn conjugate(&self) -> Self {
+        Quaternion {
+            w: self.w,
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+
+    pub fn rotate(&self, v: &Vec3) -> Vec3 {
+        let u = vec3(self.x, self.y, self.z);
+        let t = 2.0 * cross(&u, v);
+        v + self.w * t + cross(&u, &t)
+    }
+
+    /// Spherical linear interpolation along the shorter arc.
+    pub fn slerp(&self, other: &Quaternion, t: f64) -> Self {
+        let mut cos_theta = self.dot(other);
+        let mut end = *other;
+        if cos_theta < 0.0 {
+            cos_theta = -cos_theta;
+            end = Quaternion {
+                w: -end.w,
+                x: -end.x,
+                y: -end.y,
+                z: -end.z,
+            };
+        }
+
+        // Nearly parallel: fall back to a normalised lerp to avoid dividing by ~0.
+        let (a, b) = if cos_theta > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = cos_theta.acos();
+            let sin_theta = theta.sin();
+            (
+                ((1.0 - t) * theta).sin() / sin_theta,
+                (t * theta).sin() / sin_theta,
+            )
+        };
+        Quaternion {
+            w: a * self.w + b * end.w,
+            x: a * self.x + b * end.x,
+            y: a * self.y + b * end.y,
+            z: a * self.z + b * end.z,
+        }
+        .normalized()
+    }
+
+    pub fn to_matrix(self) -> Mat4 {
+        let (w, x, y, z) = (self.w, self.x, self.y, self.z);
+        Mat4 {
+            m: [
+                [
+                    1.0 - 2.0 * (y * y + z * z),
+                    2.0 * (x * y - w * z),
+                    2.0 * (x * z + w * y),
+                    0.0,
+                ],
+                [
+                    2.0 * (x * y + w * z),
+                    1.0 - 2.0 * (x * x + z * z),
+                    2.0 * (y * z - w * x),
+                    0.0,
+                ],
+                [
+                    2.0 * (x * z - w * y),
+                    2.0 * (y * z + w * x),
+                    1.0 - 2.0 * (x * x + y * y),
+                    0.0,
+                ],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+}
+
+/// Hamilton product; `a * b` rotates by `b` first, then by `a`.
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, other: Quaternion) -> Quaternion {
+        Quaternion {
+            w: self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+            x: self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            y: self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            z: self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: &Vec3, b: &Vec3) {
+        assert!((*a - *b).length() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn matrix_rotates_like_the_quaternion() {
+        let q = Quaternion::from_euler(0.3, -1.2, 2.0);
+        let v = vec3(1.0, 2.0, -0.5);
+        assert_near(&q.rotate(&v), &q.to_matrix().transform_vector(&v));
+    }
+
+    #[test]
+    fn look_rotation_turns_minus_z_forward() {
+        let forward = vec3(1.0, -1.0, -2.0);
+        let q = Quaternion::look_rotation(&forward, &vec3(0.0, 1.0, 0.0));
+        assert_near(&q.rotate(&vec3(0.0, 0.0, -1.0)), &unit_vector(forward));
+    }
+
+    #[test]
+    fn slerp_hits_its_endpoints() {
+        let a = Quaternion::from_axis_angle(&vec3(0.0, 1.0, 0.0), 0.2);
+        let b = Quaternion::from_axis_angle(&vec3(1.0, 1.0, 0.0), 2.5);
+        let v = vec3(0.3, -0.7, 1.1);
+        assert_near(&a.slerp(&b, 0.0).rotate(&v), &a.rotate(&v));
+        assert_near(&a.slerp(&b, 1.0).rotate(&v), &b.rotate(&v));
+    }
+
+    #[test]
+    fn slerp_between_antipodes_stays_put() {
+        // q and -q are the same rotation, so the shorter arc between them is empty.
+        let q = Quaternion::from_axis_angle(&vec3(0.0, 0.0, 1.0), 1.0);
+        let minus_q = Quaternion {
+            w: -q.w,
+            x: -q.x,
+            y: -q.y,
+            z: -q.z,
+        };
+        let v = vec3(1.0, 0.0, 0.0);
+        for &t in &[0.0, 0.5, 1.0] {
+            assert_near(&q.slerp(&minus_q, t).rotate(&v), &q.rotate(&v));
+        }
+    }
+}
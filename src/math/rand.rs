@@ -8,6 +8,7 @@ use std::{
     time::Instant,
 };
 
+use super::constants::PI;
 use super::vec3::{dot, unit_vector, vec3, Vec3, Vector};
 
 #[derive(Debug)]
@@ -30,11 +31,10 @@ impl Rng {
 
     #[inline]
     pub const fn with_seed(seed: u64) -> Self {
-        Self { 0: Cell::new(seed) }
+        Self(Cell::new(seed))
     }
 
     #[inline]
-
     pub fn get_seed(&self) -> u64 {
         self.0.get()
     }
@@ -290,3 +290,10 @@ pub fn random_in_unit_disk() -> Vec3 {
         return p;
     }
 }
+
+/// Standard normal sample, by the Box-Muller transform.
+pub fn random_normal() -> f64 {
+    let u1 = 1.0 - random_f64(0.0, 1.0);
+    let u2 = random_f64(0.0, 1.0);
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
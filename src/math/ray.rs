@@ -1,14 +1,19 @@
-use crate::shapes::HittableList;
+use crate::medium::{Medium, MediumEvent};
+use crate::shapes::{HitRecord, HittableList};
 
 use super::{
     constants::INFINITY,
-    vec3::{color, unit_vector, ColorRGB, Point3, Vec3, Vector},
+    rand::random_f64,
+    spectrum::{SampledSpectrum, SampledWavelengths},
+    vec3::{color, dot, unit_vector, ColorRGB, Point3, Vec3, Vector},
 };
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Ray {
     point: Point3,
     direction: Vec3,
+    /// Moment within the camera's shutter interval the ray was sent at.
+    time: f64,
 }
 
 impl Ray {
@@ -20,38 +25,219 @@ impl Ray {
         self.direction
     }
 
+    pub fn time(self) -> f64 {
+        self.time
+    }
+
     pub fn at(self, t: f64) -> Point3 {
         self.point + self.direction * t
     }
 }
 
-pub fn new_ray(point: &Point3, direction: &Vec3) -> Ray {
+pub fn new_ray(point: &Point3, direction: &Vec3, time: f64) -> Ray {
     Ray {
         point: *point,
         direction: *direction,
+        time,
     }
 }
 
-pub fn ray_color(ray: &Ray, world: &mut HittableList, depth: i32) -> ColorRGB {
+pub fn ray_color(ray: &Ray, world: &HittableList, depth: i32) -> ColorRGB {
     if depth <= 0 {
         return color(0.0, 0.0, 0.0);
     }
 
-    match world.hit(ray, 0.001, INFINITY) {
-        Some(rec) => {
-            let material = &rec.material;
-            match material.scatter(ray, &rec) {
-                Some((attenuation, scattered)) => {
-                    return attenuation * ray_color(&scattered, world, depth - 1);
+    let surface = world.hit(ray, 0.001, INFINITY);
+
+    // Outside of objects rays travel through the scene's atmosphere, if any.
+    let mut transmittance = color(1.0, 1.0, 1.0);
+    if let Some(atmosphere) = &world.atmosphere {
+        let t_max = surface.as_ref().map_or(INFINITY, |rec| rec.t);
+        // A ray leaving the scene sees the sky whether or not it scatters on
+        // the way. Like a shadow ray it takes the sky in dimmed by the
+        // atmosphere's transmittance, and a scattered path adds to that.
+        let sky = match surface {
+            Some(_) => color(0.0, 0.0, 0.0),
+            None => atmosphere.transmittance(ray, INFINITY) * background(ray),
+        };
+        match atmosphere.sample(ray, t_max) {
+            MediumEvent::Scattered { t, weight } => {
+                let scattered = new_ray(
+                    &ray.at(t),
+                    &atmosphere.sample_direction(&ray.direction()),
+                    ray.time(),
+                );
+                return sky + weight * ray_color(&scattered, world, depth - 1);
+            }
+            MediumEvent::Passed { .. } if surface.is_none() => return sky,
+            MediumEvent::Passed { weight } => transmittance = weight,
+        }
+    }
+
+    if let Some(rec) = surface {
+        let material = &rec.material;
+        if let Some((attenuation, scattered)) = material.scatter(ray, &rec) {
+            let throughput = transmittance * attenuation;
+            if let Some(medium) = material.interior() {
+                if rec.front_face && crosses_surface(&rec, &scattered) {
+                    return throughput * random_walk(&scattered, world, medium, depth - 1);
                 }
+            }
+            return throughput * ray_color(&scattered, world, depth - 1);
+        }
+        return color(0.0, 0.0, 0.0);
+    }
+    return transmittance * background(ray);
+}
 
-                None => {}
+/// Whether a scattered ray went through the surface rather than bouncing off it.
+fn crosses_surface(rec: &HitRecord, scattered: &Ray) -> bool {
+    dot(&scattered.direction(), &rec.normal) < 0.0
+}
+
+/// Follows a ray travelling inside an object filled with `medium`, scattering
+/// inside it until the ray leaves through the object's surface.
+fn random_walk(ray: &Ray, world: &HittableList, medium: &dyn Medium, depth: i32) -> ColorRGB {
+    if depth <= 0 {
+        return color(0.0, 0.0, 0.0);
+    }
+
+    let surface = world.hit(ray, 0.001, INFINITY);
+    let t_max = surface.as_ref().map_or(INFINITY, |rec| rec.t);
+
+    match medium.sample(ray, t_max) {
+        MediumEvent::Scattered { t, weight } => {
+            let scattered = new_ray(
+                &ray.at(t),
+                &medium.sample_direction(&ray.direction()),
+                ray.time(),
+            );
+            return weight * random_walk(&scattered, world, medium, depth - 1);
+        }
+        MediumEvent::Passed { weight } => {
+            let rec = match surface {
+                Some(rec) => rec,
+                None => return weight * background(ray),
+            };
+            if let Some((attenuation, scattered)) = rec.material.scatter(ray, &rec) {
+                let throughput = weight * attenuation;
+                if crosses_surface(&rec, &scattered) {
+                    return throughput * ray_color(&scattered, world, depth - 1);
+                }
+                return throughput * random_walk(&scattered, world, medium, depth - 1);
             }
             return color(0.0, 0.0, 0.0);
         }
+    }
+}
+
+/// Spectral counterpart of `ray_color`. A fresh set of wavelengths is sampled
+/// for every camera ray and the carried radiance is converted back to RGB at
+/// the end, so the result can be accumulated exactly like `ray_color`'s.
+pub fn ray_color_spectral(ray: &Ray, world: &HittableList, depth: i32) -> ColorRGB {
+    let mut wavelengths = SampledWavelengths::sample_uniform(random_f64(0.0, 1.0));
+    let radiance = trace_spectral(ray, world, &mut wavelengths, depth);
+    return radiance.to_rgb(&wavelengths);
+}
 
-        None => {}
+fn trace_spectral(
+    ray: &Ray,
+    world: &HittableList,
+    wavelengths: &mut SampledWavelengths,
+    depth: i32,
+) -> SampledSpectrum {
+    if depth <= 0 {
+        return SampledSpectrum::constant(0.0);
+    }
+
+    let surface = world.hit(ray, 0.001, INFINITY);
+
+    let mut transmittance = SampledSpectrum::constant(1.0);
+    if let Some(atmosphere) = &world.atmosphere {
+        let t_max = surface.as_ref().map_or(INFINITY, |rec| rec.t);
+        let sky = match surface {
+            Some(_) => SampledSpectrum::constant(0.0),
+            None => SampledSpectrum::from_rgb(
+                atmosphere.transmittance(ray, INFINITY) * background(ray),
+                wavelengths,
+            ),
+        };
+        match atmosphere.sample_spectral(ray, t_max, wavelengths) {
+            MediumEvent::Scattered { t, weight } => {
+                let scattered = new_ray(
+                    &ray.at(t),
+                    &atmosphere.sample_direction(&ray.direction()),
+                    ray.time(),
+                );
+                return sky + weight * trace_spectral(&scattered, world, wavelengths, depth - 1);
+            }
+            MediumEvent::Passed { .. } if surface.is_none() => return sky,
+            MediumEvent::Passed { weight } => transmittance = weight,
+        }
+    }
+
+    if let Some(rec) = surface {
+        let material = &rec.material;
+        if let Some((attenuation, scattered)) = material.scatter_spectral(ray, &rec, wavelengths) {
+            let throughput = transmittance * attenuation;
+            if let Some(medium) = material.interior() {
+                if rec.front_face && crosses_surface(&rec, &scattered) {
+                    return throughput
+                        * random_walk_spectral(&scattered, world, medium, wavelengths, depth - 1);
+                }
+            }
+            return throughput * trace_spectral(&scattered, world, wavelengths, depth - 1);
+        }
+        return SampledSpectrum::constant(0.0);
+    }
+    return transmittance * SampledSpectrum::from_rgb(background(ray), wavelengths);
+}
+
+fn random_walk_spectral(
+    ray: &Ray,
+    world: &HittableList,
+    medium: &dyn Medium,
+    wavelengths: &mut SampledWavelengths,
+    depth: i32,
+) -> SampledSpectrum {
+    if depth <= 0 {
+        return SampledSpectrum::constant(0.0);
+    }
+
+    let surface = world.hit(ray, 0.001, INFINITY);
+    let t_max = surface.as_ref().map_or(INFINITY, |rec| rec.t);
+
+    match medium.sample_spectral(ray, t_max, wavelengths) {
+        MediumEvent::Scattered { t, weight } => {
+            let scattered = new_ray(
+                &ray.at(t),
+                &medium.sample_direction(&ray.direction()),
+                ray.time(),
+            );
+            return weight
+                * random_walk_spectral(&scattered, world, medium, wavelengths, depth - 1);
+        }
+        MediumEvent::Passed { weight } => {
+            let rec = match surface {
+                Some(rec) => rec,
+                None => return weight * SampledSpectrum::from_rgb(background(ray), wavelengths),
+            };
+            if let Some((attenuation, scattered)) =
+                rec.material.scatter_spectral(ray, &rec, wavelengths)
+            {
+                let throughput = weight * attenuation;
+                if crosses_surface(&rec, &scattered) {
+                    return throughput * trace_spectral(&scattered, world, wavelengths, depth - 1);
+                }
+                return throughput
+                    * random_walk_spectral(&scattered, world, medium, wavelengths, depth - 1);
+            }
+            return SampledSpectrum::constant(0.0);
+        }
     }
+}
+
+fn background(ray: &Ray) -> ColorRGB {
     let unit_direction = unit_vector(ray.direction());
     let t = (unit_direction.y() + 1.0) * 0.5;
     return ColorRGB {
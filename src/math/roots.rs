@@ -0,0 +1,160 @@
+// Real roots of low degree polynomials, used by the analytic shapes.
+// Every solver returns its roots in ascending order.
+
+use super::constants::PI;
+
+/// Roots of a x² + b x + c.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return if b == 0.0 { vec![] } else { vec![-c / b] };
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return vec![];
+    }
+    // Avoids the cancellation of -b ± sqrt when b² is much larger than 4ac.
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    let mut roots = if q == 0.0 {
+        vec![0.0, 0.0]
+    } else {
+        vec![q / a, c / q]
+    };
+    roots.sort_by(|x, y| x.total_cmp(y));
+    roots
+}
+
+/// Roots of x³ + a x² + b x + c.
+pub fn solve_cubic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    // Depressed to y³ + p y + q with x = y - a / 3.
+    let shift = a / 3.0;
+    let p = b - a * shift;
+    let q = c - b * shift + 2.0 * shift * shift * shift;
+    let half_q = 0.5 * q;
+    let third_p = p / 3.0;
+    let discriminant = half_q * half_q + third_p * third_p * third_p;
+
+    let mut roots = if discriminant > 0.0 {
+        let sqrt = discriminant.sqrt();
+        vec![(-half_q + sqrt).cbrt() + (-half_q - sqrt).cbrt()]
+    } else if third_p == 0.0 {
+        vec![0.0]
+    } else {
+        // Three real roots, found with the trigonometric method.
+        let r = (-third_p).sqrt();
+        let phi = (-half_q / (r * r * r)).clamp(-1.0, 1.0).acos() / 3.0;
+        let third_turn = 2.0 * PI / 3.0;
+        vec![
+            2.0 * r * phi.cos(),
+            2.0 * r * (phi - third_turn).cos(),
+            2.0 * r * (phi + third_turn).cos(),
+        ]
+    };
+    for root in roots.iter_mut() {
+        *root -= shift;
+    }
+    roots.sort_by(|x, y| x.total_cmp(y));
+    roots
+}
+
+/// Roots of x⁴ + a x³ + b x² + c x + d, using Ferrari's method followed by
+/// a couple of Newton steps to win back the precision it loses.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    // Depressed to y⁴ + p y² + q y + r with x = y - a / 4.
+    let shift = a / 4.0;
+    let a2 = a * a;
+    let p = b - 3.0 * a2 / 8.0;
+    let q = c - 0.5 * a * b + a2 * a / 8.0;
+    let r = d - 0.25 * a * c + a2 * b / 16.0 - 3.0 * a2 * a2 / 256.0;
+
+    let mut roots = Vec::with_capacity(4);
+    if q.abs() < 1e-12 {
+        // Biquadratic, a quadratic in y².
+        for z in solve_quadratic(1.0, p, r) {
+            if z >= 0.0 {
+                roots.push(z.sqrt());
+                roots.push(-z.sqrt());
+            }
+        }
+    } else {
+        // Any positive root m of the resolvent cubic splits the quartic
+        // into two quadratics; the largest root is always positive here.
+        let m = *solve_cubic(p, 0.25 * p * p - r, -q * q / 8.0)
+            .last()
+            .unwrap();
+        if m <= 0.0 {
+            return vec![];
+        }
+        let s = (2.0 * m).sqrt();
+        let offset = q / (2.0 * s);
+        roots.extend(solve_quadratic(1.0, -s, 0.5 * p + m + offset));
+        roots.extend(solve_quadratic(1.0, s, 0.5 * p + m - offset));
+    }
+
+    for root in roots.iter_mut() {
+        let mut x = *root - shift;
+        for _ in 0..2 {
+            let f = (((x + a) * x + b) * x + c) * x + d;
+            let df = ((4.0 * x + 3.0 * a) * x + 2.0 * b) * x + c;
+            if df == 0.0 {
+                break;
+            }
+            x -= f / df;
+        }
+        *root = x;
+    }
+    roots.sort_by(|x, y| x.total_cmp(y));
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roots(roots: &[f64], expected: &[f64]) {
+        assert_eq!(roots.len(), expected.len(), "{:?} != {:?}", roots, expected);
+        for (root, want) in roots.iter().zip(expected) {
+            assert!((root - want).abs() < 1e-6, "{:?} != {:?}", roots, expected);
+        }
+    }
+
+    #[test]
+    fn cubic_with_one_or_three_real_roots() {
+        assert_roots(&solve_cubic(0.0, 0.0, -8.0), &[2.0]);
+        // (x - 1)(x - 2)(x + 3)
+        assert_roots(&solve_cubic(0.0, -7.0, 6.0), &[-3.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn quartic_with_four_real_roots() {
+        // (x + 1)(x - 1)(x - 2)(x - 5)
+        assert_roots(
+            &solve_quartic(-7.0, 9.0, 7.0, -10.0),
+            &[-1.0, 1.0, 2.0, 5.0],
+        );
+    }
+
+    #[test]
+    fn quartic_with_a_double_root() {
+        // (x - 1)²(x + 2)(x - 3): the double root may come back once or twice.
+        let mut roots = solve_quartic(-3.0, -3.0, 11.0, -6.0);
+        roots.dedup_by(|x, y| (*x - *y).abs() < 1e-4);
+        assert_roots(&roots, &[-2.0, 1.0, 3.0]);
+    }
+
+    #[test]
+    fn quartic_without_real_roots() {
+        // (x² + 1)(x² + 2x + 5)
+        assert!(solve_quartic(2.0, 6.0, 2.0, 5.0).is_empty());
+    }
+
+    #[test]
+    fn biquadratic_quartic() {
+        // (x - 1)(x - 2)(x - 3)(x - 4) is symmetric about 2.5, so its
+        // depressed form has no odd terms.
+        assert_roots(
+            &solve_quartic(-10.0, 35.0, -50.0, 24.0),
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+        assert!(solve_quartic(0.0, 0.0, 0.0, 1.0).is_empty());
+    }
+}
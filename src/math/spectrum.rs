@@ -0,0 +1,316 @@
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign};
+
+use super::vec3::{color, vec3, ColorRGB, Vec3, Vector};
+
+// Spectral support for the hero wavelength pipeline.
+// Radiance is carried as a handful of wavelength samples instead of RGB,
+// RGB inputs are upsampled with Smits' method and the result is
+// projected onto the CIE 1931 observer before being turned into sRGB.
+
+pub const LAMBDA_MIN: f64 = 360.0;
+pub const LAMBDA_MAX: f64 = 830.0;
+
+/// Number of wavelengths carried along each path (the hero plus its rotations).
+pub const N_SPECTRUM_SAMPLES: usize = 4;
+
+/// Integral of the fitted CIE y-bar curve over [LAMBDA_MIN, LAMBDA_MAX].
+const CIE_Y_INTEGRAL: f64 = 106.922_074_506_916_32;
+
+/// Linear sRGB of the equal-energy spectrum, used to white balance the output
+/// so that a constant spectrum of 1 comes back as `color(1.0, 1.0, 1.0)`.
+const EQUAL_ENERGY_WHITE: Vec3 = Vec3 {
+    x: 1.200_268_165_015_651_3,
+    y: 0.949_698_991_488_175_8,
+    z: 0.908_295_967_868_092,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SampledWavelengths {
+    lambda: [f64; N_SPECTRUM_SAMPLES],
+    pdf: [f64; N_SPECTRUM_SAMPLES],
+}
+
+impl SampledWavelengths {
+    /// Hero wavelength sampling: `u` picks the hero uniformly over the visible
+    /// range and the remaining wavelengths are spaced evenly around it.
+    pub fn sample_uniform(u: f64) -> Self {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        let hero = LAMBDA_MIN + u * range;
+        let delta = range / N_SPECTRUM_SAMPLES as f64;
+
+        let mut lambda = [hero; N_SPECTRUM_SAMPLES];
+        for (i, l) in lambda.iter_mut().enumerate().skip(1) {
+            *l += i as f64 * delta;
+            if *l > LAMBDA_MAX {
+                *l = LAMBDA_MIN + (*l - LAMBDA_MAX);
+            }
+        }
+
+        SampledWavelengths {
+            lambda,
+            pdf: [1.0 / range; N_SPECTRUM_SAMPLES],
+        }
+    }
+
+    pub fn lambda(&self, i: usize) -> f64 {
+        self.lambda[i]
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.lambda[0]
+    }
+
+    pub fn pdf(&self) -> SampledSpectrum {
+        SampledSpectrum { values: self.pdf }
+    }
+
+    /// Drops every wavelength but the hero, for wavelength dependent events
+    /// such as dispersion where the secondaries can no longer follow the path.
+    pub fn terminate_secondary(&mut self) {
+        if self.secondary_terminated() {
+            return;
+        }
+        for pdf in self.pdf.iter_mut().skip(1) {
+            *pdf = 0.0;
+        }
+        self.pdf[0] /= N_SPECTRUM_SAMPLES as f64;
+    }
+
+    pub fn secondary_terminated(&self) -> bool {
+        self.pdf.iter().skip(1).all(|&pdf| pdf == 0.0)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SampledSpectrum {
+    values: [f64; N_SPECTRUM_SAMPLES],
+}
+
+impl SampledSpectrum {
+    pub fn constant(c: f64) -> Self {
+        SampledSpectrum {
+            values: [c; N_SPECTRUM_SAMPLES],
+        }
+    }
+
+    /// Upsamples an RGB albedo to the given wavelengths.
+    pub fn from_rgb(rgb: ColorRGB, wavelengths: &SampledWavelengths) -> Self {
+        let mut values = [0.0; N_SPECTRUM_SAMPLES];
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = rgb_to_spectrum(rgb, wavelengths.lambda(i));
+        }
+        SampledSpectrum { values }
+    }
+
+    pub fn from_values(values: [f64; N_SPECTRUM_SAMPLES]) -> Self {
+        SampledSpectrum { values }
+    }
+
+    pub fn values(&self) -> [f64; N_SPECTRUM_SAMPLES] {
+        self.values
+    }
+
+    pub fn value(&self, i: usize) -> f64 {
+        self.values[i]
+    }
+
+    pub fn set_value(&mut self, i: usize, value: f64) {
+        self.values[i] = value;
+    }
+
+    pub fn is_black(&self) -> bool {
+        self.values.iter().all(|&v| v == 0.0)
+    }
+
+    pub fn average(&self) -> f64 {
+        self.values.iter().sum::<f64>() / N_SPECTRUM_SAMPLES as f64
+    }
+
+    /// Monte Carlo estimate of the CIE XYZ tristimulus values, normalised so
+    /// that a constant spectrum of 1 has a luminance of 1.
+    pub fn to_xyz(self, wavelengths: &SampledWavelengths) -> Vec3 {
+        let mut xyz = vec3(0.0, 0.0, 0.0);
+        for i in 0..N_SPECTRUM_SAMPLES {
+            let pdf = wavelengths.pdf[i];
+            if pdf == 0.0 {
+                continue;
+            }
+            let lambda = wavelengths.lambda(i);
+            let weight = self.values[i] / pdf;
+            xyz += weight * vec3(cie_x(lambda), cie_y(lambda), cie_z(lambda));
+        }
+        xyz / (N_SPECTRUM_SAMPLES as f64 * CIE_Y_INTEGRAL)
+    }
+
+    /// White balanced linear sRGB, ready to be accumulated like any other sample.
+    pub fn to_rgb(self, wavelengths: &SampledWavelengths) -> ColorRGB {
+        xyz_to_linear_srgb(self.to_xyz(wavelengths)) / EQUAL_ENERGY_WHITE
+    }
+}
+
+impl Add for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn add(self, other: SampledSpectrum) -> SampledSpectrum {
+        let mut values = self.values;
+        for (v, o) in values.iter_mut().zip(other.values.iter()) {
+            *v += o;
+        }
+        SampledSpectrum { values }
+    }
+}
+
+impl AddAssign for SampledSpectrum {
+    fn add_assign(&mut self, other: SampledSpectrum) {
+        *self = *self + other;
+    }
+}
+
+impl Mul for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn mul(self, other: SampledSpectrum) -> SampledSpectrum {
+        let mut values = self.values;
+        for (v, o) in values.iter_mut().zip(other.values.iter()) {
+            *v *= o;
+        }
+        SampledSpectrum { values }
+    }
+}
+
+impl MulAssign for SampledSpectrum {
+    fn mul_assign(&mut self, other: SampledSpectrum) {
+        *self = *self * other;
+    }
+}
+
+impl Mul<f64> for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn mul(self, other: f64) -> SampledSpectrum {
+        self * SampledSpectrum::constant(other)
+    }
+}
+
+impl Mul<SampledSpectrum> for f64 {
+    type Output = SampledSpectrum;
+
+    fn mul(self, other: SampledSpectrum) -> SampledSpectrum {
+        other * self
+    }
+}
+
+impl Div<f64> for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn div(self, other: f64) -> SampledSpectrum {
+        self * (1.0 / other)
+    }
+}
+
+// CIE 1931 colour matching functions, using the multi-lobe Gaussian fit from
+// Wyman, Sloan and Shirley, "Simple Analytic Approximations to the CIE XYZ
+// Color Matching Functions" (2013).
+
+fn piecewise_gaussian(lambda: f64, mu: f64, sigma_low: f64, sigma_high: f64) -> f64 {
+    let sigma = if lambda < mu { sigma_low } else { sigma_high };
+    let t = (lambda - mu) / sigma;
+    (-0.5 * t * t).exp()
+}
+
+pub fn cie_x(lambda: f64) -> f64 {
+    1.056 * piecewise_gaussian(lambda, 599.8, 37.9, 31.0)
+        + 0.362 * piecewise_gaussian(lambda, 442.0, 16.0, 26.7)
+        - 0.065 * piecewise_gaussian(lambda, 501.1, 20.4, 26.2)
+}
+
+pub fn cie_y(lambda: f64) -> f64 {
+    0.821 * piecewise_gaussian(lambda, 568.8, 46.9, 40.5)
+        + 0.286 * piecewise_gaussian(lambda, 530.9, 16.3, 31.1)
+}
+
+pub fn cie_z(lambda: f64) -> f64 {
+    1.217 * piecewise_gaussian(lambda, 437.0, 11.8, 36.0)
+        + 0.681 * piecewise_gaussian(lambda, 459.0, 26.0, 13.8)
+}
+
+pub fn xyz_to_linear_srgb(xyz: Vec3) -> ColorRGB {
+    color(
+        3.240_454_2 * xyz.x() - 1.537_138_5 * xyz.y() - 0.498_531_4 * xyz.z(),
+        -0.969_266 * xyz.x() + 1.876_010_8 * xyz.y() + 0.041_556 * xyz.z(),
+        0.055_643_4 * xyz.x() - 0.204_025_9 * xyz.y() + 1.057_225_2 * xyz.z(),
+    )
+}
+
+// Smits, "An RGB to Spectrum Conversion for Reflectances" (1999).
+// Basis spectra tabulated in 10 bins between 380nm and 720nm.
+
+const SMITS_LAMBDA_MIN: f64 = 380.0;
+const SMITS_LAMBDA_MAX: f64 = 720.0;
+const SMITS_BINS: usize = 10;
+
+const SMITS_WHITE: [f64; SMITS_BINS] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f64; SMITS_BINS] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f64; SMITS_BINS] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f64; SMITS_BINS] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f64; SMITS_BINS] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f64; SMITS_BINS] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f64; SMITS_BINS] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+/// Linearly interpolates a Smits basis spectrum between its bin centres.
+fn smits_basis(basis: &[f64; SMITS_BINS], lambda: f64) -> f64 {
+    let bin_width = (SMITS_LAMBDA_MAX - SMITS_LAMBDA_MIN) / SMITS_BINS as f64;
+    let x = (lambda - SMITS_LAMBDA_MIN) / bin_width - 0.5;
+    if x <= 0.0 {
+        return basis[0];
+    }
+    if x >= (SMITS_BINS - 1) as f64 {
+        return basis[SMITS_BINS - 1];
+    }
+    let i = x as usize;
+    let t = x - i as f64;
+    basis[i] * (1.0 - t) + basis[i + 1] * t
+}
+
+/// Evaluates the Smits reflectance spectrum for `rgb` at a single wavelength.
+pub fn rgb_to_spectrum(rgb: ColorRGB, lambda: f64) -> f64 {
+    let (r, g, b) = (rgb.x(), rgb.y(), rgb.z());
+    let s = |basis: &[f64; SMITS_BINS]| smits_basis(basis, lambda);
+
+    if r <= g && r <= b {
+        let base = r * s(&SMITS_WHITE);
+        if g <= b {
+            base + (g - r) * s(&SMITS_CYAN) + (b - g) * s(&SMITS_BLUE)
+        } else {
+            base + (b - r) * s(&SMITS_CYAN) + (g - b) * s(&SMITS_GREEN)
+        }
+    } else if g <= r && g <= b {
+        let base = g * s(&SMITS_WHITE);
+        if r <= b {
+            base + (r - g) * s(&SMITS_MAGENTA) + (b - r) * s(&SMITS_BLUE)
+        } else {
+            base + (b - g) * s(&SMITS_MAGENTA) + (r - b) * s(&SMITS_RED)
+        }
+    } else {
+        let base = b * s(&SMITS_WHITE);
+        if r <= g {
+            base + (r - b) * s(&SMITS_YELLOW) + (g - r) * s(&SMITS_GREEN)
+        } else {
+            base + (g - b) * s(&SMITS_YELLOW) + (r - g) * s(&SMITS_RED)
+        }
+    }
+}
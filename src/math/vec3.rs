@@ -1,6 +1,6 @@
 use super::rand::random_f64;
 
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub, SubAssign};
 
 use crate::{impl_binary_operations, impl_op_assign, impl_unary_operations};
 
@@ -62,19 +62,31 @@ impl Vector for Vec3 {
     }
 }
 
+impl Index<usize> for Vec3 {
+    type Output = f64;
+
+    fn index(&self, axis: usize) -> &f64 {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            _ => &self.z,
+        }
+    }
+}
+
 pub type ColorRGB = Vec3;
 pub type Point3 = Vec3;
 
 pub fn color(x: f64, y: f64, z: f64) -> ColorRGB {
-    ColorRGB { x: x, y: y, z: z }
+    ColorRGB { x, y, z }
 }
 
 pub fn point(x: f64, y: f64, z: f64) -> Point3 {
-    Point3 { x: x, y: y, z: z }
+    Point3 { x, y, z }
 }
 
 pub fn vec3(x: f64, y: f64, z: f64) -> Vec3 {
-    Vec3 { x: x, y: y, z: z }
+    Vec3 { x, y, z }
 }
 
 pub fn unit_vector(vec3: Vec3) -> Vec3 {
@@ -93,6 +105,14 @@ pub fn cross(r: &Vec3, other: &Vec3) -> Vec3 {
     };
 }
 
+pub fn luminance(color: ColorRGB) -> f64 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+pub fn lerp(a: Vec3, b: Vec3, t: f64) -> Vec3 {
+    (1.0 - t) * a + t * b
+}
+
 // MACROS
 
 impl_binary_operations!(Vec3 Add add +);
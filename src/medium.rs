@@ -0,0 +1,489 @@
+use crate::load::VoxelGrid;
+use crate::math::constants::PI;
+use crate::math::onb::Onb;
+use crate::math::rand::random_f64;
+use crate::math::ray::Ray;
+use crate::math::spectrum::{SampledSpectrum, SampledWavelengths};
+use crate::math::vec3::{color, unit_vector, vec3, ColorRGB, Point3, Vec3, Vector};
+use crate::shapes::aabb::Aabb;
+
+// Participating media. Coefficients are per unit length. The random walk in
+// `ray_color` finds the next surface and then asks the medium whether the
+// ray interacts with it before getting there.
+
+/// Outcome of flying through a medium towards the next surface.
+pub enum MediumEvent<T> {
+    /// The ray scattered at parameter `t`; the path is multiplied by `weight`.
+    Scattered { t: f64, weight: T },
+    /// The ray reached the surface; the path is multiplied by `weight`.
+    Passed { weight: T },
+}
+
+pub trait Medium: Send + Sync {
+    /// Samples an interaction along `ray` before parameter `t_max`.
+    fn sample(&self, ray: &Ray, t_max: f64) -> MediumEvent<ColorRGB>;
+
+    fn sample_spectral(
+        &self,
+        ray: &Ray,
+        t_max: f64,
+        wavelengths: &SampledWavelengths,
+    ) -> MediumEvent<SampledSpectrum>;
+
+    /// New direction after scattering from a ray travelling along `direction`.
+    fn sample_direction(&self, direction: &Vec3) -> Vec3;
+
+    /// Fraction of light getting through along `ray` up to `t_max`, as needed
+    /// by shadow rays. `ray_color` uses it for sky light seen through the
+    /// atmosphere.
+    fn transmittance(&self, ray: &Ray, t_max: f64) -> ColorRGB;
+}
+
+// Homogeneous media may have different coefficients per colour channel, so
+// free-flight distances are sampled along one channel picked at random and
+// weighted by the average pdf over all channels (one-sample MIS). The helpers
+// work the same on RGB triples and on the spectral renderer's wavelengths.
+
+#[derive(Debug, Copy, Clone)]
+pub struct HomogeneousMedium {
+    pub scattering: ColorRGB,
+    pub absorption: ColorRGB,
+    /// Henyey-Greenstein asymmetry, -1 (back scattering) to 1 (forward scattering).
+    pub anisotropy: f64,
+}
+
+/// Medium with a uniform `density` whose scattering to extinction ratio is
+/// `albedo`; white gives fog that only scatters, black gives soot.
+pub fn homogeneous_medium(density: f64, albedo: ColorRGB, anisotropy: f64) -> HomogeneousMedium {
+    HomogeneousMedium {
+        scattering: density * albedo,
+        absorption: density * (color(1.0, 1.0, 1.0) - albedo),
+        anisotropy,
+    }
+}
+
+impl HomogeneousMedium {
+    pub fn extinction(&self) -> ColorRGB {
+        self.scattering + self.absorption
+    }
+}
+
+impl Medium for HomogeneousMedium {
+    fn sample(&self, ray: &Ray, t_max: f64) -> MediumEvent<ColorRGB> {
+        match sample_homogeneous(
+            ray,
+            t_max,
+            &to_array(self.scattering),
+            &to_array(self.extinction()),
+        ) {
+            MediumEvent::Scattered { t, weight } => MediumEvent::Scattered {
+                t,
+                weight: from_array(weight),
+            },
+            MediumEvent::Passed { weight } => MediumEvent::Passed {
+                weight: from_array(weight),
+            },
+        }
+    }
+
+    fn sample_spectral(
+        &self,
+        ray: &Ray,
+        t_max: f64,
+        wavelengths: &SampledWavelengths,
+    ) -> MediumEvent<SampledSpectrum> {
+        let scattering = SampledSpectrum::from_rgb(self.scattering, wavelengths).values();
+        let extinction = SampledSpectrum::from_rgb(self.extinction(), wavelengths).values();
+        match sample_homogeneous(ray, t_max, &scattering, &extinction) {
+            MediumEvent::Scattered { t, weight } => MediumEvent::Scattered {
+                t,
+                weight: SampledSpectrum::from_values(weight),
+            },
+            MediumEvent::Passed { weight } => MediumEvent::Passed {
+                weight: SampledSpectrum::from_values(weight),
+            },
+        }
+    }
+
+    fn sample_direction(&self, direction: &Vec3) -> Vec3 {
+        sample_henyey_greenstein(direction, self.anisotropy)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_max: f64) -> ColorRGB {
+        let distance = t_max * ray.direction().length();
+        from_array(to_array(self.extinction()).map(|sigma_t| beer_lambert(sigma_t, distance)))
+    }
+}
+
+fn to_array(v: ColorRGB) -> [f64; 3] {
+    [v.x(), v.y(), v.z()]
+}
+
+fn from_array(a: [f64; 3]) -> ColorRGB {
+    color(a[0], a[1], a[2])
+}
+
+fn beer_lambert(sigma_t: f64, distance: f64) -> f64 {
+    if sigma_t <= 0.0 {
+        1.0
+    } else {
+        (-sigma_t * distance).exp()
+    }
+}
+
+fn sample_homogeneous<const N: usize>(
+    ray: &Ray,
+    t_max: f64,
+    scattering: &[f64; N],
+    extinction: &[f64; N],
+) -> MediumEvent<[f64; N]> {
+    let speed = ray.direction().length();
+    let t = sample_free_flight(extinction) / speed;
+    if t < t_max {
+        MediumEvent::Scattered {
+            t,
+            weight: scatter_weight(scattering, extinction, t * speed),
+        }
+    } else {
+        MediumEvent::Passed {
+            weight: pass_weight(extinction, t_max * speed),
+        }
+    }
+}
+
+/// Samples a free-flight distance along a randomly chosen channel.
+fn sample_free_flight<const N: usize>(extinction: &[f64; N]) -> f64 {
+    let channel = ((random_f64(0.0, 1.0) * N as f64) as usize).min(N - 1);
+    let sigma_t = extinction[channel];
+    if sigma_t <= 0.0 {
+        return f64::INFINITY;
+    }
+    -(1.0 - random_f64(0.0, 1.0)).ln() / sigma_t
+}
+
+/// Path weight for a real scattering event at distance `distance`.
+fn scatter_weight<const N: usize>(
+    scattering: &[f64; N],
+    extinction: &[f64; N],
+    distance: f64,
+) -> [f64; N] {
+    let transmittance = extinction.map(|sigma_t| beer_lambert(sigma_t, distance));
+    let mut pdf = 0.0;
+    for i in 0..N {
+        pdf += extinction[i] * transmittance[i] / N as f64;
+    }
+    let mut weight = [0.0; N];
+    for i in 0..N {
+        weight[i] = if pdf > 0.0 {
+            scattering[i] * transmittance[i] / pdf
+        } else {
+            0.0
+        };
+    }
+    weight
+}
+
+/// Path weight for flying `distance` through the medium without scattering.
+fn pass_weight<const N: usize>(extinction: &[f64; N], distance: f64) -> [f64; N] {
+    let transmittance = extinction.map(|sigma_t| beer_lambert(sigma_t, distance));
+    let pdf = transmittance.iter().sum::<f64>() / N as f64;
+    transmittance.map(|tr| if pdf > 0.0 { tr / pdf } else { 0.0 })
+}
+
+/// Smoke and clouds from a dense voxel grid of densities stretched over
+/// `bounds`. Extinction is `density_scale` times the trilinearly interpolated
+/// density and is the same for every channel, with `albedo` colouring the
+/// scattered light. Interactions are found with delta (Woodcock) tracking
+/// against the grid's maximum density, and transmittance with ratio tracking.
+pub struct GridMedium {
+    pub grid: VoxelGrid,
+    pub bounds: Aabb,
+    pub density_scale: f64,
+    pub albedo: ColorRGB,
+    pub anisotropy: f64,
+    /// Majorant extinction used for tracking.
+    max_extinction: f64,
+}
+
+pub fn grid_medium(
+    grid: VoxelGrid,
+    bounds: Aabb,
+    density_scale: f64,
+    albedo: ColorRGB,
+    anisotropy: f64,
+) -> GridMedium {
+    let max_density = grid.values.iter().fold(0.0f32, |max, &d| max.max(d));
+    GridMedium {
+        max_extinction: max_density as f64 * density_scale,
+        grid,
+        bounds,
+        density_scale,
+        albedo,
+        anisotropy,
+    }
+}
+
+impl GridMedium {
+    pub fn extinction(&self, p: &Point3) -> f64 {
+        self.density(p) * self.density_scale
+    }
+
+    /// Trilinearly interpolated density, zero outside the bounds.
+    pub fn density(&self, p: &Point3) -> f64 {
+        let size = self.bounds.maximum - self.bounds.minimum;
+        let local = (p - self.bounds.minimum) / size;
+        if local.x() < 0.0
+            || local.y() < 0.0
+            || local.z() < 0.0
+            || local.x() > 1.0
+            || local.y() > 1.0
+            || local.z() > 1.0
+        {
+            return 0.0;
+        }
+
+        // Voxel values sit at cell centres.
+        let coordinate = |u: f64, n: usize| {
+            let x = (u * n as f64 - 0.5).max(0.0).min((n - 1) as f64);
+            let i = (x as usize).min(n.saturating_sub(2));
+            (i, (i + 1).min(n - 1), x - i as f64)
+        };
+        let (i0, i1, tx) = coordinate(local.x(), self.grid.nx);
+        let (j0, j1, ty) = coordinate(local.y(), self.grid.ny);
+        let (k0, k1, tz) = coordinate(local.z(), self.grid.nz);
+
+        let v = |i, j, k| self.grid.value(i, j, k) as f64;
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let c00 = lerp(v(i0, j0, k0), v(i1, j0, k0), tx);
+        let c10 = lerp(v(i0, j1, k0), v(i1, j1, k0), tx);
+        let c01 = lerp(v(i0, j0, k1), v(i1, j0, k1), tx);
+        let c11 = lerp(v(i0, j1, k1), v(i1, j1, k1), tx);
+        lerp(lerp(c00, c10, ty), lerp(c01, c11, ty), tz)
+    }
+
+    /// Delta tracking: tentative collisions are sampled against the majorant
+    /// and accepted as real with probability extinction / majorant.
+    fn delta_track(&self, ray: &Ray, t_max: f64) -> Option<f64> {
+        if self.max_extinction <= 0.0 {
+            return None;
+        }
+        let (mut t, t_exit) = self.bounds.hit(ray, 0.0, t_max)?;
+        let majorant = self.max_extinction * ray.direction().length();
+        loop {
+            t -= (1.0 - random_f64(0.0, 1.0)).ln() / majorant;
+            if t >= t_exit {
+                return None;
+            }
+            if random_f64(0.0, 1.0) * self.max_extinction < self.extinction(&ray.at(t)) {
+                return Some(t);
+            }
+        }
+    }
+}
+
+impl Medium for GridMedium {
+    fn sample(&self, ray: &Ray, t_max: f64) -> MediumEvent<ColorRGB> {
+        match self.delta_track(ray, t_max) {
+            Some(t) => MediumEvent::Scattered {
+                t,
+                weight: self.albedo,
+            },
+            None => MediumEvent::Passed {
+                weight: color(1.0, 1.0, 1.0),
+            },
+        }
+    }
+
+    fn sample_spectral(
+        &self,
+        ray: &Ray,
+        t_max: f64,
+        wavelengths: &SampledWavelengths,
+    ) -> MediumEvent<SampledSpectrum> {
+        match self.delta_track(ray, t_max) {
+            Some(t) => MediumEvent::Scattered {
+                t,
+                weight: SampledSpectrum::from_rgb(self.albedo, wavelengths),
+            },
+            None => MediumEvent::Passed {
+                weight: SampledSpectrum::constant(1.0),
+            },
+        }
+    }
+
+    fn sample_direction(&self, direction: &Vec3) -> Vec3 {
+        sample_henyey_greenstein(direction, self.anisotropy)
+    }
+
+    /// Ratio tracking: an unbiased transmittance estimate that multiplies in
+    /// the null collision probability at each tentative collision.
+    fn transmittance(&self, ray: &Ray, t_max: f64) -> ColorRGB {
+        let (mut t, t_exit) = match self.bounds.hit(ray, 0.0, t_max) {
+            Some(interval) if self.max_extinction > 0.0 => interval,
+            _ => return color(1.0, 1.0, 1.0),
+        };
+        let majorant = self.max_extinction * ray.direction().length();
+        let mut transmittance = 1.0;
+        loop {
+            t -= (1.0 - random_f64(0.0, 1.0)).ln() / majorant;
+            if t >= t_exit {
+                break;
+            }
+            transmittance *= 1.0 - self.extinction(&ray.at(t)) / self.max_extinction;
+        }
+        color(transmittance, transmittance, transmittance)
+    }
+}
+
+/// Aerial perspective for large outdoor scenes: fog whose extinction is
+/// `density` at `base_height` and falls off exponentially with height at
+/// rate `falloff` per unit. A falloff of zero gives uniform fog everywhere.
+/// Extinction is the same for every channel, with `albedo` colouring the
+/// scattered light. The optical depth along a ray has a closed form, so
+/// distances are sampled exactly and rays heading up can escape the fog.
+#[derive(Debug, Copy, Clone)]
+pub struct HeightFog {
+    pub density: f64,
+    pub falloff: f64,
+    pub base_height: f64,
+    pub albedo: ColorRGB,
+    pub anisotropy: f64,
+}
+
+impl HeightFog {
+    /// Extinction per unit of ray parameter at the ray origin, and its rate of
+    /// exponential decay along the ray.
+    fn extinction_along(&self, ray: &Ray) -> (f64, f64) {
+        let origin_height = ray.origin().y() - self.base_height;
+        let start = self.density * ray.direction().length() * (-self.falloff * origin_height).exp();
+        (start, self.falloff * ray.direction().y())
+    }
+
+    /// Optical depth from the ray origin up to parameter `t`.
+    fn optical_depth(&self, ray: &Ray, t: f64) -> f64 {
+        let (start, decay) = self.extinction_along(ray);
+        if start <= 0.0 {
+            return 0.0;
+        }
+        if decay.abs() < 1e-9 {
+            return start * t;
+        }
+        start * (1.0 - (-decay * t).exp()) / decay
+    }
+
+    /// Inverts the optical depth to find where a collision happens, if it does.
+    fn sample_distance(&self, ray: &Ray, t_max: f64) -> Option<f64> {
+        let (start, decay) = self.extinction_along(ray);
+        if start <= 0.0 {
+            return None;
+        }
+        let depth = -(1.0 - random_f64(0.0, 1.0)).ln();
+        let t = if decay.abs() < 1e-9 {
+            depth / start
+        } else {
+            let remaining = 1.0 - depth * decay / start;
+            if remaining <= 0.0 {
+                // Not enough fog left above the ray to ever collide.
+                return None;
+            }
+            -remaining.ln() / decay
+        };
+        if t < t_max {
+            Some(t)
+        } else {
+            None
+        }
+    }
+}
+
+impl Medium for HeightFog {
+    fn sample(&self, ray: &Ray, t_max: f64) -> MediumEvent<ColorRGB> {
+        match self.sample_distance(ray, t_max) {
+            Some(t) => MediumEvent::Scattered {
+                t,
+                weight: self.albedo,
+            },
+            None => MediumEvent::Passed {
+                weight: color(1.0, 1.0, 1.0),
+            },
+        }
+    }
+
+    fn sample_spectral(
+        &self,
+        ray: &Ray,
+        t_max: f64,
+        wavelengths: &SampledWavelengths,
+    ) -> MediumEvent<SampledSpectrum> {
+        match self.sample_distance(ray, t_max) {
+            Some(t) => MediumEvent::Scattered {
+                t,
+                weight: SampledSpectrum::from_rgb(self.albedo, wavelengths),
+            },
+            None => MediumEvent::Passed {
+                weight: SampledSpectrum::constant(1.0),
+            },
+        }
+    }
+
+    fn sample_direction(&self, direction: &Vec3) -> Vec3 {
+        sample_henyey_greenstein(direction, self.anisotropy)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_max: f64) -> ColorRGB {
+        let transmittance = (-self.optical_depth(ray, t_max)).exp();
+        color(transmittance, transmittance, transmittance)
+    }
+}
+
+/// Samples a new direction around `direction` from the Henyey-Greenstein phase
+/// function. The phase function is sampled exactly, so the path weight is 1.
+pub fn sample_henyey_greenstein(direction: &Vec3, g: f64) -> Vec3 {
+    let u1 = random_f64(0.0, 1.0);
+    let u2 = random_f64(0.0, 1.0);
+    let cos_theta = if g.abs() < 1e-3 {
+        1.0 - 2.0 * u1
+    } else {
+        let sqr = (1.0 - g * g) / (1.0 + g - 2.0 * g * u1);
+        (1.0 + g * g - sqr * sqr) / (2.0 * g)
+    };
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * u2;
+    let local = vec3(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+    Onb::build_from_w(&unit_vector(*direction)).local(&local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::ray::new_ray;
+    use crate::shapes::aabb::aabb;
+
+    #[test]
+    fn ratio_tracking_matches_beer_lambert_on_a_constant_grid() {
+        let grid = VoxelGrid {
+            nx: 2,
+            ny: 2,
+            nz: 2,
+            values: vec![0.5; 8],
+        };
+        let bounds = aabb(vec3(0.0, 0.0, 0.0), vec3(1.0, 1.0, 1.0));
+        let medium = grid_medium(grid, bounds, 4.0, color(1.0, 1.0, 1.0), 0.0);
+
+        // Crosses one unit of the grid, starting outside it and ending beyond.
+        let ray = new_ray(&vec3(-1.0, 0.5, 0.5), &vec3(2.0, 0.0, 0.0), 0.0);
+        let runs = 20000;
+        let estimate = (0..runs)
+            .map(|_| medium.transmittance(&ray, 10.0).x())
+            .sum::<f64>()
+            / runs as f64;
+        assert!((estimate - (-2.0f64).exp()).abs() < 0.015, "{}", estimate);
+
+        // Stopping halfway through only covers half the optical depth.
+        let estimate = (0..runs)
+            .map(|_| medium.transmittance(&ray, 0.75).x())
+            .sum::<f64>()
+            / runs as f64;
+        assert!((estimate - (-1.0f64).exp()).abs() < 0.015, "{}", estimate);
+    }
+}
@@ -0,0 +1,62 @@
+// Trowbridge-Reitz (GGX) microfacet helpers shared by the glossy materials.
+// `alpha` is the usual roughness squared; directions are expected to be unit
+// length and on the same side as the shading normal unless stated otherwise.
+
+use crate::math::constants::PI;
+use crate::math::onb::Onb;
+use crate::math::rand::random_f64;
+use crate::math::vec3::{color, vec3, ColorRGB, Vec3};
+
+/// Keeps perfectly smooth surfaces from producing a degenerate distribution.
+pub const MIN_ALPHA: f64 = 1e-3;
+
+pub fn roughness_to_alpha(roughness: f64) -> f64 {
+    (roughness * roughness).max(MIN_ALPHA)
+}
+
+/// Smith masking term for a single direction.
+pub fn smith_g1(n_dot_v: f64, alpha: f64) -> f64 {
+    let n_dot_v = n_dot_v.abs();
+    let a2 = alpha * alpha;
+    2.0 * n_dot_v / (n_dot_v + (a2 + (1.0 - a2) * n_dot_v * n_dot_v).sqrt())
+}
+
+/// Separable Smith masking-shadowing G(l, v).
+pub fn smith_g(n_dot_l: f64, n_dot_v: f64, alpha: f64) -> f64 {
+    smith_g1(n_dot_l, alpha) * smith_g1(n_dot_v, alpha)
+}
+
+/// Samples a microfacet normal proportionally to D(h) * (n . h).
+pub fn sample_ggx_half_vector(normal: &Vec3, alpha: f64) -> Vec3 {
+    let u1 = random_f64(0.0, 1.0);
+    let u2 = random_f64(0.0, 1.0);
+    let theta = (alpha * (u1 / (1.0 - u1)).sqrt()).atan();
+    let phi = 2.0 * PI * u2;
+    let sin_theta = theta.sin();
+    let local = vec3(sin_theta * phi.cos(), sin_theta * phi.sin(), theta.cos());
+    Onb::build_from_w(normal).local(&local)
+}
+
+/// Weight `f * cos / pdf` of a microfacet reflection or refraction whose half
+/// vector was drawn with `sample_ggx_half_vector`, excluding the Fresnel term.
+pub fn ggx_sample_weight(
+    n_dot_l: f64,
+    n_dot_v: f64,
+    n_dot_h: f64,
+    v_dot_h: f64,
+    alpha: f64,
+) -> f64 {
+    if n_dot_v <= 0.0 || n_dot_h <= 0.0 {
+        return 0.0;
+    }
+    smith_g(n_dot_l, n_dot_v, alpha) * v_dot_h.abs() / (n_dot_v * n_dot_h)
+}
+
+pub fn schlick_weight(cosine: f64) -> f64 {
+    (1.0 - cosine).clamp(0.0, 1.0).powi(5)
+}
+
+/// Schlick's approximation with a coloured reflectance at normal incidence.
+pub fn fresnel_schlick(cosine: f64, f0: ColorRGB) -> ColorRGB {
+    f0 + (color(1.0, 1.0, 1.0) - f0) * schlick_weight(cosine)
+}
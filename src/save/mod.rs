@@ -2,8 +2,7 @@ use std::fs::File;
 
 use std::io::BufWriter;
 use std::io::{Result, Write};
-use std::iter::Inspect;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use crate::math::base::clamp;
 use crate::math::vec3::{ColorRGB, Vector};
@@ -26,10 +25,11 @@ pub fn save_color(
     let mut b = color.z();
 
     //Divide the color by the number of samples.
+    //Spectral renders can land slightly outside the sRGB gamut, so clamp negatives before the gamma.
     let scale = 1.0 / samples_per_pixel as f64;
-    r = (scale * r).sqrt();
-    g = (scale * g).sqrt();
-    b = (scale * b).sqrt();
+    r = (scale * r).max(0.0).sqrt();
+    g = (scale * g).max(0.0).sqrt();
+    b = (scale * b).max(0.0).sqrt();
 
     writeln!(
         file,
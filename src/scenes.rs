@@ -0,0 +1,640 @@
+use std::io::Result;
+use std::sync::Arc;
+
+use crate::camera::Camera;
+use crate::load::{read_height_grid, read_height_map, read_obj, read_voxel_grid};
+use crate::material::{
+    hair, mix, principled, thin_film, Coated, Cutout, Dielectric, Dispersive, Lambertian, Material,
+    Metal, Mix, OrenNayar, Principled, Subsurface,
+};
+use crate::math::constants::{INFINITY, PI};
+use crate::math::matrix::Mat4;
+use crate::math::quaternion::Quaternion;
+use crate::math::rand::random_f64;
+use crate::math::vec3::{color, point, unit_vector, vec3, ColorRGB, Vector};
+use crate::medium::{grid_medium, homogeneous_medium, HeightFog};
+use crate::shapes::aabb::aabb;
+use crate::shapes::bvh::bvh;
+use crate::shapes::capsule::capsule;
+use crate::shapes::cone::cone;
+use crate::shapes::constant_medium::constant_medium;
+use crate::shapes::csg::{csg_difference, csg_intersection, csg_union};
+use crate::shapes::cuboid::cuboid;
+use crate::shapes::curve::{curve, CurveMode};
+use crate::shapes::cylinder::cylinder;
+use crate::shapes::disk::disk;
+use crate::shapes::displacement::{displace, ScreenTessellation};
+use crate::shapes::grid_volume::grid_volume;
+use crate::shapes::heightfield::heightfield;
+use crate::shapes::plane::plane;
+use crate::shapes::quad::quad;
+use crate::shapes::sdf::{
+    repeat, sdf, sdf_box, sdf_sphere, sdf_torus, smooth_union, translate, twist, Sdf,
+};
+use crate::shapes::sphere::moving_sphere;
+use crate::shapes::subdivision::{loop_subdivide, Crease};
+use crate::shapes::torus::torus;
+use crate::shapes::transformed::{animated_transformed, pose, transformed};
+use crate::shapes::tri_mesh::{tri_mesh, MeshData};
+use crate::shapes::{sphere, Hittable, HittableList};
+use crate::texture::{solid_color, Checker, ImageTexture, Texture};
+
+// Demo scenes, each with a camera framing it. `main` picks one to render.
+
+fn lambertian(albedo: ColorRGB) -> Arc<dyn Material> {
+    Arc::new(Lambertian { albedo })
+}
+
+fn empty_world() -> HittableList {
+    HittableList {
+        objects: vec![],
+        atmosphere: None,
+    }
+}
+
+/// The cover of Ray Tracing in One Weekend: lots of small random spheres
+/// around three big ones.
+pub fn random_spheres(aspect_ratio: f64) -> (HittableList, Camera) {
+    let mut world = empty_world();
+    let ground_material = lambertian(color(0.5, 0.5, 0.5));
+    world.add(Arc::new(sphere(
+        point(0.0, -1000.0, 0.0),
+        1000.0,
+        ground_material,
+    )));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat = random_f64(0.0, 1.0);
+            let center = point(
+                a as f64 + 0.9 * random_f64(0.0, 1.0),
+                0.2,
+                b as f64 + 0.9 * random_f64(0.0, 1.0),
+            );
+
+            if (center - point(4.0, 0.2, 0.0)).length() > 0.9 {
+                let sphere_material: Arc<dyn Material>;
+                if choose_mat < 0.8 {
+                    // diffuse
+                    let albedo = ColorRGB::random(0.0, 1.0) * ColorRGB::random(0.0, 1.0);
+                    sphere_material = Arc::new(Lambertian { albedo });
+                    world.add(Arc::new(sphere(center, 0.2, sphere_material)));
+                } else if choose_mat < 0.95 {
+                    // metal
+                    let albedo = ColorRGB::random(0.5, 1.0);
+                    let fuzz = random_f64(0.0, 0.5);
+                    sphere_material = Arc::new(Metal {
+                        albedo,
+                        roughness: fuzz,
+                    });
+                    world.add(Arc::new(sphere(center, 0.2, sphere_material)));
+                } else {
+                    // glass
+                    sphere_material = Arc::new(Dielectric {
+                        index_of_refraction: 1.5,
+                    });
+                    world.add(Arc::new(sphere(center, 0.2, sphere_material)));
+                }
+            }
+        }
+    }
+
+    let material1 = Arc::new(Dielectric {
+        index_of_refraction: 1.5,
+    });
+    world.add(Arc::new(sphere(point(0.0, 1.0, 0.0), 1.0, material1)));
+
+    let material2 = lambertian(color(0.4, 0.2, 0.1));
+    world.add(Arc::new(sphere(point(-4.0, 1.0, 0.0), 1.0, material2)));
+
+    let material3 = Arc::new(Metal {
+        albedo: color(0.7, 0.6, 0.5),
+        roughness: 0.0,
+    });
+    world.add(Arc::new(sphere(point(4.0, 1.0, 0.0), 1.0, material3)));
+
+    let lookfrom = point(13.0, 2.0, 3.0);
+    let lookat = point(0.0, 0.0, 0.0);
+    let dist_to_focus = 10.0;
+    let camera = Camera::new(
+        lookfrom,
+        lookat,
+        vec3(0.0, 1.0, 0.0),
+        20.0,
+        aspect_ratio,
+        0.1,
+        dist_to_focus,
+    );
+    (world, camera)
+}
+
+/// Rows of spheres, one per material, for comparing them side by side.
+pub fn materials(aspect_ratio: f64) -> Result<(HittableList, Camera)> {
+    let mut world = empty_world();
+    // A rough floor, like plaster or sand.
+    world.add(Arc::new(plane(
+        point(0.0, 0.0, 0.0),
+        vec3(0.0, 1.0, 0.0),
+        Arc::new(OrenNayar {
+            albedo: color(0.6, 0.6, 0.55),
+            sigma: 0.5,
+        }),
+    )));
+
+    let checker = |frequency: f64| -> Arc<dyn Texture> {
+        Arc::new(Checker {
+            even: solid_color(color(1.0, 1.0, 1.0)),
+            odd: solid_color(color(0.0, 0.0, 0.0)),
+            frequency,
+        })
+    };
+
+    let materials: Vec<Arc<dyn Material>> = vec![
+        // Splits white light into its colours when rendered with `spectral`.
+        Arc::new(Dispersive {
+            index_of_refraction: 1.6,
+            abbe_number: 30.0,
+        }),
+        Arc::new(Principled {
+            metallic: 1.0,
+            roughness: 0.2,
+            ..principled(color(1.0, 0.78, 0.34))
+        }),
+        Arc::new(Principled {
+            metallic: 1.0,
+            roughness: 0.5,
+            ..principled(color(0.9, 0.9, 0.9))
+        }),
+        Arc::new(Principled {
+            transmission: 1.0,
+            roughness: 0.05,
+            ..principled(color(1.0, 1.0, 1.0))
+        }),
+        // Velvet with a layer of lacquer.
+        Arc::new(Principled {
+            sheen: 1.0,
+            subsurface: 0.5,
+            specular_tint: 0.3,
+            clearcoat: 0.5,
+            ..principled(color(0.5, 0.05, 0.2))
+        }),
+        // Red paint under a blue-tinted varnish.
+        Arc::new(Coated {
+            base: lambertian(color(0.6, 0.1, 0.1)),
+            index_of_refraction: 1.5,
+            roughness: 0.05,
+            absorption: color(0.4, 0.2, 0.0),
+            thickness: 0.5,
+        }),
+        // Checks of polished metal and blue paint.
+        Arc::new(Mix {
+            weight: checker(10.0),
+            ..mix(
+                Arc::new(Metal {
+                    albedo: color(0.8, 0.8, 0.8),
+                    roughness: 0.1,
+                }),
+                lambertian(color(0.1, 0.3, 0.6)),
+                0.5,
+            )
+        }),
+        // A perforated shell, with the holes punched by an image.
+        Arc::new(Cutout {
+            material: lambertian(color(0.8, 0.5, 0.1)),
+            opacity: Arc::new(ImageTexture::load("assets/perforated.ppm")?),
+        }),
+        // A soap bubble, and a petrol film on black paint.
+        Arc::new(thin_film(
+            Arc::new(Dielectric {
+                index_of_refraction: 1.0,
+            }),
+            380.0,
+            1.33,
+            1.0,
+        )),
+        Arc::new(thin_film(
+            lambertian(color(0.02, 0.02, 0.02)),
+            450.0,
+            1.45,
+            1.6,
+        )),
+        // Jade, lit from within by light scattering under its surface.
+        Arc::new(Subsurface {
+            index_of_refraction: 1.6,
+            medium: homogeneous_medium(8.0, color(0.6, 0.95, 0.7), 0.3),
+        }),
+    ];
+    // Rows of six going back, each shifted sideways to show between the
+    // spheres in front of it.
+    for (i, material) in materials.into_iter().enumerate() {
+        let (row, column) = ((i / 6) as f64, (i % 6) as f64);
+        let center = point(-5.0 + 2.0 * column + row, 0.8, -2.0 * row);
+        world.add(Arc::new(sphere(center, 0.8, material)));
+    }
+
+    // A tuft of hair in front, thick strands as tubes and fine ones as
+    // ribbons, and a patch of grass blades that turn as they rise.
+    let auburn: Arc<dyn Material> = Arc::new(hair(color(0.6, 0.25, 0.1)));
+    let mut strands: Vec<Arc<dyn Hittable>> = vec![];
+    for i in 0..200 {
+        let angle = random_f64(0.0, 2.0 * PI);
+        let (sin, cos) = angle.sin_cos();
+        let spread = random_f64(0.0, 0.3);
+        let root = point(-3.0 + spread * cos, 0.0, 2.5 + spread * sin);
+        let out = vec3(cos, 0.0, sin);
+        let points = [
+            root,
+            root + vec3(0.0, 0.6, 0.0) + 0.2 * out,
+            root + vec3(0.0, 0.9, 0.0) + 0.6 * out,
+            root + vec3(0.0, 0.7, 0.0) + 1.0 * out,
+        ];
+        let (width, mode) = if i % 2 == 0 {
+            (0.02, CurveMode::Tube)
+        } else {
+            (0.01, CurveMode::Ribbon)
+        };
+        strands.push(Arc::new(curve(
+            points,
+            width,
+            0.2 * width,
+            mode,
+            auburn.clone(),
+        )));
+    }
+    world.add(Arc::new(bvh(strands)));
+
+    let leaf = lambertian(color(0.25, 0.55, 0.15));
+    let mut blades: Vec<Arc<dyn Hittable>> = vec![];
+    for _ in 0..400 {
+        let root = point(random_f64(1.5, 4.5), 0.0, random_f64(2.0, 3.0));
+        let facing = random_f64(0.0, 2.0 * PI);
+        let bend = vec3(facing.cos(), 0.0, facing.sin());
+        let height = random_f64(0.3, 0.6);
+        let points = [
+            root,
+            root + vec3(0.0, 0.5 * height, 0.0),
+            root + vec3(0.0, 0.9 * height, 0.0) + 0.1 * bend,
+            root + vec3(0.0, height, 0.0) + 0.25 * bend,
+        ];
+        let mode = CurveMode::OrientedRibbon {
+            normal0: bend,
+            normal1: unit_vector(bend + vec3(0.0, 1.0, 0.0)),
+        };
+        blades.push(Arc::new(curve(points, 0.04, 0.005, mode, leaf.clone())));
+    }
+    world.add(Arc::new(bvh(blades)));
+
+    let camera = Camera::new(
+        point(0.0, 5.0, 11.0),
+        point(0.0, 0.4, -1.0),
+        vec3(0.0, 1.0, 0.0),
+        40.0,
+        aspect_ratio,
+        0.0,
+        12.0,
+    );
+    Ok((world, camera))
+}
+
+/// Smoke and clouds over open ground.
+pub fn volumes(aspect_ratio: f64) -> Result<(HittableList, Camera)> {
+    let mut world = empty_world();
+    world.add(Arc::new(plane(
+        point(0.0, 0.0, 0.0),
+        vec3(0.0, 1.0, 0.0),
+        lambertian(color(0.35, 0.5, 0.25)),
+    )));
+
+    // A puff of white smoke and a plume of soot.
+    let boundary =
+        |center, radius| Arc::new(sphere(center, radius, lambertian(color(1.0, 1.0, 1.0))));
+    world.add(Arc::new(constant_medium(
+        boundary(point(-2.5, 2.0, -4.0), 1.2),
+        homogeneous_medium(1.5, color(0.8, 0.8, 0.8), 0.0),
+    )));
+    world.add(Arc::new(constant_medium(
+        boundary(point(-4.5, 1.0, -6.0), 1.0),
+        homogeneous_medium(2.0, color(0.1, 0.1, 0.1), 0.0),
+    )));
+
+    // Hills from a height map behind everything, and a low ridge from a raw
+    // height grid in front.
+    let grass = lambertian(color(0.3, 0.45, 0.2));
+    if let Some(hills) = heightfield(
+        read_height_map("assets/hills.pgm")?,
+        point(-16.0, 0.0, -22.0),
+        vec3(32.0, 4.0, 14.0),
+        grass.clone(),
+    ) {
+        world.add(Arc::new(hills));
+    }
+    if let Some(ridge) = heightfield(
+        read_height_grid("assets/ridge.raw")?,
+        point(1.0, 0.0, 0.0),
+        vec3(5.0, 0.8, 1.5),
+        grass,
+    ) {
+        world.add(Arc::new(ridge));
+    }
+
+    // A lumpy cloud with ripples of density through it.
+    world.add(Arc::new(grid_volume(grid_medium(
+        read_voxel_grid("assets/cloud.raw")?,
+        aabb(point(0.5, 1.5, -6.0), point(4.5, 4.0, -2.0)),
+        4.0,
+        color(0.95, 0.95, 0.95),
+        0.5,
+    ))));
+
+    // Valley fog thinning out with height.
+    world.atmosphere = Some(Arc::new(HeightFog {
+        density: 0.004,
+        falloff: 0.5,
+        base_height: 0.0,
+        albedo: color(0.9, 0.9, 0.95),
+        anisotropy: 0.3,
+    }));
+
+    let camera = Camera::new(
+        point(0.0, 3.0, 8.0),
+        point(0.0, 1.5, -4.0),
+        vec3(0.0, 1.0, 0.0),
+        40.0,
+        aspect_ratio,
+        0.0,
+        12.0,
+    );
+    Ok((world, camera))
+}
+
+/// Square pyramid one unit tall standing on the unit square around the origin.
+fn pyramid() -> MeshData {
+    MeshData {
+        positions: vec![
+            point(-1.0, 0.0, -1.0),
+            point(1.0, 0.0, -1.0),
+            point(1.0, 0.0, 1.0),
+            point(-1.0, 0.0, 1.0),
+            point(0.0, 1.0, 0.0),
+        ],
+        indices: vec![
+            [0, 1, 4],
+            [1, 2, 4],
+            [2, 3, 4],
+            [3, 0, 4],
+            [0, 2, 1],
+            [0, 3, 2],
+        ],
+        normals: None,
+        uvs: None,
+    }
+}
+
+/// Takes the image width as well, since displacement tessellates to the
+/// size of the mesh on screen.
+pub fn shapes(aspect_ratio: f64, image_width: i32) -> Result<(HittableList, Camera)> {
+    let lookfrom = point(0.0, 5.0, 14.0);
+    let rotation =
+        Quaternion::look_rotation(&(point(0.0, 1.0, 0.0) - lookfrom), &vec3(0.0, 1.0, 0.0));
+    let camera = Camera::from_rotation(lookfrom, &rotation, 40.0, aspect_ratio, 0.0, 14.0)
+        .with_shutter(0.0, 1.0);
+
+    let mut world = empty_world();
+    world.add(Arc::new(plane(
+        point(0.0, 0.0, 0.0),
+        vec3(0.0, 1.0, 0.0),
+        lambertian(color(0.5, 0.5, 0.5)),
+    )));
+
+    // One unit sphere shared by squashed, tilted instances of it.
+    let unit_sphere: Arc<dyn Hittable> = Arc::new(sphere(
+        point(0.0, 0.0, 0.0),
+        1.0,
+        lambertian(color(0.8, 0.3, 0.2)),
+    ));
+    for (i, tilt) in [-0.6, 0.0, 0.6].iter().enumerate() {
+        let transform = Mat4::translation(&vec3(-6.0 + 1.8 * i as f64, 1.0, 0.0))
+            * Mat4::rotation_z(*tilt)
+            * Mat4::rotation_x(0.4)
+            * Mat4::scaling(&vec3(0.5, 0.9, 0.5));
+        if let Some(ellipsoid) = transformed(unit_sphere.clone(), transform) {
+            world.add(Arc::new(ellipsoid));
+        }
+    }
+
+    // Flat primitives: a backdrop wall, a standing disk and a box.
+    world.add(Arc::new(quad(
+        point(-8.0, 0.0, -5.0),
+        vec3(9.0, 0.0, 0.0),
+        vec3(0.0, 3.0, 0.0),
+        lambertian(color(0.75, 0.7, 0.6)),
+    )));
+    world.add(Arc::new(disk(
+        point(-6.0, 1.2, -3.0),
+        vec3(0.3, 0.0, 1.0),
+        1.0,
+        Arc::new(Metal {
+            albedo: color(0.8, 0.8, 0.85),
+            roughness: 0.05,
+        }),
+    )));
+    world.add(Arc::new(cuboid(
+        point(-4.2, 0.0, -3.6),
+        point(-3.0, 1.2, -2.4),
+        lambertian(color(0.2, 0.3, 0.7)),
+    )));
+
+    // Quadrics: a post, a cone, a ring, a capsule and a pipe on its side.
+    world.add(Arc::new(cylinder(
+        point(-0.5, 0.0, 0.0),
+        0.5,
+        1.5,
+        true,
+        lambertian(color(0.8, 0.6, 0.2)),
+    )));
+    world.add(Arc::new(cone(
+        point(1.2, 0.0, 0.0),
+        0.6,
+        1.6,
+        true,
+        lambertian(color(0.6, 0.2, 0.6)),
+    )));
+    world.add(Arc::new(torus(
+        point(3.0, 0.3, 0.3),
+        0.7,
+        0.3,
+        Arc::new(Metal {
+            albedo: color(0.9, 0.75, 0.4),
+            roughness: 0.2,
+        }),
+    )));
+    world.add(Arc::new(capsule(
+        point(4.8, 0.4, 0.0),
+        point(5.6, 1.4, -0.6),
+        0.4,
+        lambertian(color(0.3, 0.7, 0.7)),
+    )));
+    let pipe = Arc::new(cylinder(
+        point(0.0, 0.0, 0.0),
+        0.3,
+        2.5,
+        false,
+        lambertian(color(0.7, 0.7, 0.7)),
+    ));
+    let lying = Mat4::translation(&vec3(2.5, 0.3, 2.5)) * Mat4::rotation_z(0.5 * PI);
+    if let Some(pipe) = transformed(pipe, lying) {
+        world.add(Arc::new(pipe));
+    }
+
+    // A die: a rounded cube with pips carved out of it.
+    let ivory = lambertian(color(0.9, 0.88, 0.8));
+    let pip = |center| -> Arc<dyn Hittable> {
+        Arc::new(sphere(center, 0.1, lambertian(color(0.1, 0.1, 0.1))))
+    };
+    let pips = [
+        point(0.0, 0.5, 0.0),
+        point(-0.2, 0.2, 0.5),
+        point(0.2, -0.2, 0.5),
+        point(0.5, 0.25, -0.25),
+        point(0.5, 0.0, 0.0),
+        point(0.5, -0.25, 0.25),
+    ]
+    .iter()
+    .map(|&center| pip(center))
+    .reduce(|all, next| Arc::new(csg_union(all, next)))
+    .unwrap();
+    let blank = Arc::new(csg_intersection(
+        Arc::new(cuboid(
+            point(-0.5, -0.5, -0.5),
+            point(0.5, 0.5, 0.5),
+            ivory.clone(),
+        )),
+        Arc::new(sphere(point(0.0, 0.0, 0.0), 0.7, ivory)),
+    ));
+    let die = Arc::new(csg_difference(blank, pips));
+    let placement = Mat4::translation(&vec3(-1.8, 0.5, -2.6)) * Mat4::rotation_y(0.5);
+    if let Some(die) = transformed(die, placement) {
+        world.add(Arc::new(die));
+    }
+
+    // Distance fields: a blended blob, a row of rings and a twisted bar.
+    let blob = smooth_union(
+        translate(sdf_sphere(0.5), vec3(-5.5, 0.5, 3.0)),
+        translate(sdf_sphere(0.35), vec3(-4.9, 0.95, 3.0)),
+        0.3,
+    );
+    world.add(Arc::new(sdf(
+        blob,
+        aabb(point(-6.1, -0.1, 2.4), point(-4.4, 1.4, 3.6)),
+        lambertian(color(0.9, 0.5, 0.6)),
+    )));
+    let rings = translate(
+        repeat(sdf_torus(0.3, 0.08), vec3(0.8, 0.0, 0.0)),
+        vec3(-3.0, 0.08, 3.0),
+    );
+    world.add(Arc::new(sdf(
+        rings,
+        aabb(point(-4.2, 0.0, 2.6), point(-1.8, 0.2, 3.4)),
+        Arc::new(Metal {
+            albedo: color(0.8, 0.8, 0.8),
+            roughness: 0.1,
+        }),
+    )));
+    let bar = translate(
+        twist(sdf_box(vec3(0.2, 0.8, 0.2)), 1.5),
+        vec3(-0.8, 0.8, 3.0),
+    );
+    world.add(Arc::new(Sdf {
+        step_scale: 0.5,
+        ..sdf(
+            bar,
+            aabb(point(-1.1, 0.0, 2.7), point(-0.5, 1.6, 3.3)),
+            lambertian(color(0.4, 0.8, 0.3)),
+        )
+    }));
+
+    // Motion blur: a sphere sliding sideways and a box spinning as it rises.
+    world.add(Arc::new(moving_sphere(
+        point(0.6, 0.4, 4.0),
+        point(1.4, 0.4, 4.0),
+        0.0,
+        1.0,
+        0.4,
+        lambertian(color(0.9, 0.2, 0.2)),
+    )));
+    let crate_box = Arc::new(cuboid(
+        point(-0.4, -0.4, -0.4),
+        point(0.4, 0.4, 0.4),
+        lambertian(color(0.6, 0.45, 0.25)),
+    ));
+    world.add(Arc::new(animated_transformed(
+        crate_box,
+        pose(
+            vec3(3.8, 0.4, 4.0),
+            Quaternion::identity(),
+            vec3(1.0, 1.0, 1.0),
+        ),
+        pose(
+            vec3(3.8, 0.7, 4.0),
+            Quaternion::from_euler(0.3, 0.8, 0.0),
+            vec3(1.0, 1.0, 1.0),
+        ),
+        0.0,
+        1.0,
+    )));
+
+    // A cube cage read from an OBJ file, smoothed by subdivision except
+    // around its creased top, then roughened by displacing it in world space
+    // where its size on screen is known.
+    let cage = read_obj("assets/cage.obj")?;
+    let top = [[3, 7], [7, 6], [6, 2], [2, 3]];
+    let creases: Vec<Crease> = top
+        .iter()
+        .map(|&edge| Crease {
+            edge,
+            sharpness: INFINITY,
+        })
+        .collect();
+    let mut smooth = loop_subdivide(&cage, &creases, 2);
+    let placement = Mat4::translation(&vec3(1.2, 0.6, -3.0)) * Mat4::scaling(&vec3(0.8, 0.8, 0.8));
+    for position in smooth.positions.iter_mut() {
+        *position = placement.transform_point(position);
+    }
+    let bumps = Checker {
+        even: solid_color(color(1.0, 1.0, 1.0)),
+        odd: solid_color(color(0.0, 0.0, 0.0)),
+        frequency: 12.0,
+    };
+    let tessellation = ScreenTessellation {
+        camera: &camera,
+        image_width,
+        max_edge: 4.0,
+        max_levels: 5,
+    };
+    world.add(Arc::new(tri_mesh(
+        displace(&smooth, &bumps, 0.04, &tessellation),
+        lambertian(color(0.85, 0.55, 0.3)),
+    )));
+
+    // A grove of one shared pyramid mesh, placed many times under a BVH
+    // over the instances.
+    let tree: Arc<dyn Hittable> = Arc::new(tri_mesh(pyramid(), lambertian(color(0.2, 0.45, 0.15))));
+    let mut grove: Vec<Arc<dyn Hittable>> = vec![];
+    for row in 0..6 {
+        for column in 0..8 {
+            let height = random_f64(0.8, 1.6);
+            let transform = Mat4::translation(&vec3(
+                2.0 + 0.8 * column as f64 + random_f64(-0.2, 0.2),
+                0.0,
+                -5.0 - 0.8 * row as f64 + random_f64(-0.2, 0.2),
+            )) * Mat4::rotation_y(random_f64(0.0, 1.5))
+                * Mat4::scaling(&vec3(0.35, height, 0.35));
+            if let Some(instance) = transformed(tree.clone(), transform) {
+                grove.push(Arc::new(instance));
+            }
+        }
+    }
+    world.add(Arc::new(bvh(grove)));
+
+    Ok((world, camera))
+}
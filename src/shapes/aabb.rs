@@ -0,0 +1,100 @@
+use crate::math::ray::Ray;
+use crate::math::vec3::{vec3, Point3, Vec3, Vector};
+
+/// Axis-aligned bounding box.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Aabb {
+    pub minimum: Point3,
+    pub maximum: Point3,
+}
+
+pub fn aabb(minimum: Point3, maximum: Point3) -> Aabb {
+    Aabb { minimum, maximum }
+}
+
+pub fn surrounding_box(a: &Aabb, b: &Aabb) -> Aabb {
+    aabb(
+        vec3(
+            a.minimum.x().min(b.minimum.x()),
+            a.minimum.y().min(b.minimum.y()),
+            a.minimum.z().min(b.minimum.z()),
+        ),
+        vec3(
+            a.maximum.x().max(b.maximum.x()),
+            a.maximum.y().max(b.maximum.y()),
+            a.maximum.z().max(b.maximum.z()),
+        ),
+    )
+}
+
+/// Gives flat boxes some thickness so the slab test doesn't miss them.
+pub fn pad(bounds: Aabb) -> Aabb {
+    let delta = 1e-4;
+    let size = bounds.maximum - bounds.minimum;
+    let padding = vec3(
+        if size.x() < delta { delta } else { 0.0 },
+        if size.y() < delta { delta } else { 0.0 },
+        if size.z() < delta { delta } else { 0.0 },
+    );
+    aabb(bounds.minimum - padding, bounds.maximum + padding)
+}
+
+impl Aabb {
+    /// Slab test. Returns the parameter interval over which the ray is inside
+    /// the box, clipped to [t_min, t_max].
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let mut t_min = t_min;
+        let mut t_max = t_max;
+        for axis in 0..3 {
+            let inv_d = 1.0 / ray.direction()[axis];
+            let mut t0 = (self.minimum[axis] - ray.origin()[axis]) * inv_d;
+            let mut t1 = (self.maximum[axis] - ray.origin()[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max <= t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = self.maximum - self.minimum;
+        2.0 * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+    }
+
+    pub fn corners(&self) -> [Point3; 8] {
+        let (a, b) = (self.minimum, self.maximum);
+        [
+            vec3(a.x(), a.y(), a.z()),
+            vec3(b.x(), a.y(), a.z()),
+            vec3(a.x(), b.y(), a.z()),
+            vec3(b.x(), b.y(), a.z()),
+            vec3(a.x(), a.y(), b.z()),
+            vec3(b.x(), a.y(), b.z()),
+            vec3(a.x(), b.y(), b.z()),
+            vec3(b.x(), b.y(), b.z()),
+        ]
+    }
+
+    pub fn center(&self) -> Point3 {
+        0.5 * (self.minimum + self.maximum)
+    }
+
+    /// Normal of the face closest to a point on the box's surface.
+    pub fn outward_normal(&self, p: &Point3) -> Vec3 {
+        let half = 0.5 * (self.maximum - self.minimum);
+        let local = (p - self.center()) / half;
+        let (x, y, z) = (local.x().abs(), local.y().abs(), local.z().abs());
+        if x >= y && x >= z {
+            vec3(local.x().signum(), 0.0, 0.0)
+        } else if y >= z {
+            vec3(0.0, local.y().signum(), 0.0)
+        } else {
+            vec3(0.0, 0.0, local.z().signum())
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use std::sync::Arc;
+
+use crate::math::ray::Ray;
+
+use super::aabb::{surrounding_box, Aabb};
+use super::{HitRecord, Hittable};
+
+// Bounding volume hierarchies. `BvhTree` only knows about primitive bounding
+// boxes and is shared by `Bvh`, which groups whole shapes (e.g. a top level
+// over instances), and by `TriMesh`, which groups its own triangles as the
+// bottom level. Trees are built with the surface area heuristic over binned
+// centroids.
+
+const MAX_LEAF_SIZE: usize = 4;
+const SAH_BINS: usize = 12;
+
+enum BvhNode {
+    Leaf {
+        bounds: Aabb,
+        first: usize,
+        count: usize,
+    },
+    Interior {
+        bounds: Aabb,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl BvhNode {
+    fn bounds(&self) -> &Aabb {
+        match self {
+            BvhNode::Leaf { bounds, .. } => bounds,
+            BvhNode::Interior { bounds, .. } => bounds,
+        }
+    }
+}
+
+pub struct BvhTree {
+    nodes: Vec<BvhNode>,
+    /// Primitive indices, reordered so every leaf covers a contiguous run.
+    indices: Vec<usize>,
+}
+
+impl BvhTree {
+    pub fn build(boxes: &[Aabb]) -> Self {
+        let mut tree = BvhTree {
+            nodes: Vec::with_capacity(2 * boxes.len()),
+            indices: (0..boxes.len()).collect(),
+        };
+        if !boxes.is_empty() {
+            tree.build_node(boxes, 0, boxes.len());
+        }
+        tree
+    }
+
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.nodes.first().map(|node| *node.bounds())
+    }
+
+    fn build_node(&mut self, boxes: &[Aabb], first: usize, end: usize) -> usize {
+        let range = &self.indices[first..end];
+        let bounds = range[1..]
+            .iter()
+            .fold(boxes[range[0]], |b, &i| surrounding_box(&b, &boxes[i]));
+        let node = self.nodes.len();
+        let count = end - first;
+        self.nodes.push(BvhNode::Leaf {
+            bounds,
+            first,
+            count,
+        });
+        if count <= MAX_LEAF_SIZE {
+            return node;
+        }
+
+        let split = match self.split(boxes, first, end) {
+            Some(split) => split,
+            None => return node,
+        };
+        let left = self.build_node(boxes, first, split);
+        let right = self.build_node(boxes, split, end);
+        self.nodes[node] = BvhNode::Interior {
+            bounds,
+            left,
+            right,
+        };
+        node
+    }
+
+    /// Partitions the range along the axis and bin boundary with the lowest
+    /// SAH cost and returns where the right half starts.
+    fn split(&mut self, boxes: &[Aabb], first: usize, end: usize) -> Option<usize> {
+        let range = &mut self.indices[first..end];
+        let centroid = |i: usize| boxes[i].center();
+        let first_centroid = centroid(range[0]);
+        let centroid_bounds = range[1..].iter().fold(
+            super::aabb::aabb(first_centroid, first_centroid),
+            |b, &i| surrounding_box(&b, &super::aabb::aabb(centroid(i), centroid(i))),
+        );
+        let extent = centroid_bounds.maximum - centroid_bounds.minimum;
+        let axis = if extent[0] > extent[1] && extent[0] > extent[2] {
+            0
+        } else if extent[1] > extent[2] {
+            1
+        } else {
+            2
+        };
+        if extent[axis] <= 0.0 {
+            // Every centroid coincides; nothing to gain from splitting.
+            return None;
+        }
+
+        let bin_of = |i: usize| {
+            let offset = (centroid(i)[axis] - centroid_bounds.minimum[axis]) / extent[axis];
+            ((offset * SAH_BINS as f64) as usize).min(SAH_BINS - 1)
+        };
+        let mut bin_counts = [0usize; SAH_BINS];
+        let mut bin_bounds: [Option<Aabb>; SAH_BINS] = [None; SAH_BINS];
+        for &i in range.iter() {
+            let bin = bin_of(i);
+            bin_counts[bin] += 1;
+            bin_bounds[bin] = Some(match bin_bounds[bin] {
+                Some(b) => surrounding_box(&b, &boxes[i]),
+                None => boxes[i],
+            });
+        }
+
+        let merge = |a: Option<Aabb>, b: Option<Aabb>| match (a, b) {
+            (Some(a), Some(b)) => Some(surrounding_box(&a, &b)),
+            (a, None) => a,
+            (None, b) => b,
+        };
+        let mut best_cost = f64::INFINITY;
+        let mut best_bin = 0;
+        for split in 1..SAH_BINS {
+            let (mut left_box, mut right_box) = (None, None);
+            let (mut left_count, mut right_count) = (0, 0);
+            for bin in 0..split {
+                left_box = merge(left_box, bin_bounds[bin]);
+                left_count += bin_counts[bin];
+            }
+            for bin in split..SAH_BINS {
+                right_box = merge(right_box, bin_bounds[bin]);
+                right_count += bin_counts[bin];
+            }
+            if left_count == 0 || right_count == 0 {
+                continue;
+            }
+            let cost = left_box.map_or(0.0, |b| b.surface_area()) * left_count as f64
+                + right_box.map_or(0.0, |b| b.surface_area()) * right_count as f64;
+            if cost < best_cost {
+                best_cost = cost;
+                best_bin = split;
+            }
+        }
+
+        let mut split = 0;
+        for k in 0..range.len() {
+            if bin_of(range[k]) < best_bin {
+                range.swap(k, split);
+                split += 1;
+            }
+        }
+        if split == 0 || split == range.len() {
+            return None;
+        }
+        Some(first + split)
+    }
+
+    /// Visits the leaves the ray passes through, nearest child first, calling
+    /// `hit_primitive` with each primitive index and the closest hit so far.
+    pub fn hit<'a, F>(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        mut hit_primitive: F,
+    ) -> Option<HitRecord<'a>>
+    where
+        F: FnMut(usize, f64) -> Option<HitRecord<'a>>,
+    {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let mut closest_so_far = t_max;
+        let mut closest: Option<HitRecord<'a>> = None;
+        let mut stack = Vec::with_capacity(64);
+        stack.push(0);
+        while let Some(node) = stack.pop() {
+            match &self.nodes[node] {
+                BvhNode::Leaf {
+                    bounds,
+                    first,
+                    count,
+                } => {
+                    if bounds.hit(ray, t_min, closest_so_far).is_none() {
+                        continue;
+                    }
+                    for &primitive in &self.indices[*first..first + count] {
+                        if let Some(rec) = hit_primitive(primitive, closest_so_far) {
+                            closest_so_far = rec.t;
+                            closest = Some(rec);
+                        }
+                    }
+                }
+                BvhNode::Interior { left, right, .. } => {
+                    let left_hit = self.nodes[*left].bounds().hit(ray, t_min, closest_so_far);
+                    let right_hit = self.nodes[*right].bounds().hit(ray, t_min, closest_so_far);
+                    match (left_hit, right_hit) {
+                        (Some((l, _)), Some((r, _))) => {
+                            // Push the farther child first so the nearer is visited first.
+                            if l < r {
+                                stack.push(*right);
+                                stack.push(*left);
+                            } else {
+                                stack.push(*left);
+                                stack.push(*right);
+                            }
+                        }
+                        (Some(_), None) => stack.push(*left),
+                        (None, Some(_)) => stack.push(*right),
+                        (None, None) => {}
+                    }
+                }
+            }
+        }
+        closest
+    }
+}
+
+/// A BVH over whole shapes. Used as the top level over instances, each
+/// leaf is a `Transformed` whose object can be a mesh with its own BVH.
+/// Shapes without a bounding box are kept aside and tested on every ray.
+pub struct Bvh {
+    pub objects: Vec<Arc<dyn Hittable>>,
+    unbounded: Vec<Arc<dyn Hittable>>,
+    tree: BvhTree,
+}
+
+pub fn bvh(objects: Vec<Arc<dyn Hittable>>) -> Bvh {
+    let (bounded, unbounded): (Vec<_>, Vec<_>) = objects
+        .into_iter()
+        .partition(|object| object.bounding_box().is_some());
+    let boxes: Vec<Aabb> = bounded
+        .iter()
+        .filter_map(|object| object.bounding_box())
+        .collect();
+    Bvh {
+        tree: BvhTree::build(&boxes),
+        objects: bounded,
+        unbounded,
+    }
+}
+
+impl Hittable for Bvh {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut closest = self.tree.hit(ray, t_min, t_max, |i, closest_so_far| {
+            self.objects[i].hit(ray, t_min, closest_so_far)
+        });
+        for object in &self.unbounded {
+            let closest_so_far = closest.as_ref().map_or(t_max, |rec| rec.t);
+            if let Some(rec) = object.hit(ray, t_min, closest_so_far) {
+                closest = Some(rec);
+            }
+        }
+        closest
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        if self.unbounded.is_empty() {
+            self.tree.bounds()
+        } else {
+            None
+        }
+    }
+}
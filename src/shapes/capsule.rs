@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use crate::material::Material;
+use crate::math::constants::PI;
+use crate::math::onb::Onb;
+use crate::math::ray::Ray;
+use crate::math::roots::solve_quadratic;
+use crate::math::vec3::{dot, unit_vector, vec3, Point3, Vec3, Vector};
+
+use super::aabb::{aabb, surrounding_box, Aabb};
+use super::{HitRecord, Hittable};
+
+/// Every point within `radius` of the segment from `a` to `b`: a cylinder
+/// with hemispherical ends. u goes around the segment and v runs from the
+/// tip of the end at `a` (0) to the tip of the end at `b` (1).
+pub struct Capsule {
+    pub a: Point3,
+    pub b: Point3,
+    pub radius: f64,
+    pub material: Arc<dyn Material>,
+    axes: Onb,
+}
+
+pub fn capsule(a: Point3, b: Point3, radius: f64, material: Arc<dyn Material>) -> Capsule {
+    Capsule {
+        a,
+        b,
+        radius,
+        material,
+        axes: Onb::build_from_w(&(b - a)),
+    }
+}
+
+impl Hittable for Capsule {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let axis = self.b - self.a;
+        let length = axis.length();
+        let w = axis / length;
+        let o = ray.origin() - self.a;
+        let d = ray.direction();
+        let r2 = self.radius * self.radius;
+
+        // Side: the components perpendicular to the axis lie on a circle,
+        // and the hit must fall between the end caps.
+        let d_along = dot(&d, &w);
+        let o_along = dot(&o, &w);
+        let d_across = d - d_along * w;
+        let o_across = o - o_along * w;
+        let mut roots: Vec<f64> = solve_quadratic(
+            d_across.length_squared(),
+            2.0 * dot(&o_across, &d_across),
+            o_across.length_squared() - r2,
+        )
+        .into_iter()
+        .filter(|&t| (0.0..=length).contains(&(o_along + t * d_along)))
+        .collect();
+
+        // Ends: the halves of the end spheres lying beyond the segment.
+        for (end, beyond) in [(vec3(0.0, 0.0, 0.0), -1.0), (axis, 1.0)] {
+            let oc = o - end;
+            roots.extend(
+                solve_quadratic(
+                    d.length_squared(),
+                    2.0 * dot(&oc, &d),
+                    oc.length_squared() - r2,
+                )
+                .into_iter()
+                .filter(|&t| beyond * dot(&(oc + t * d), &w) >= 0.0),
+            );
+        }
+
+        let t = roots
+            .into_iter()
+            .filter(|&t| t >= t_min && t <= t_max)
+            .fold(None, |nearest: Option<f64>, t| {
+                Some(nearest.map_or(t, |n| n.min(t)))
+            })?;
+
+        let p = ray.at(t);
+        let along = dot(&(p - self.a), &w);
+        let closest = self.a + along.clamp(0.0, length) * w;
+        let outward_normal: Vec3 = (p - closest) / self.radius;
+        let around = dot(&outward_normal, &self.axes.v).atan2(dot(&outward_normal, &self.axes.u));
+
+        let mut rec = HitRecord {
+            p,
+            t,
+            u: (around + PI) / (2.0 * PI),
+            v: (along + self.radius) / (length + 2.0 * self.radius),
+            normal: vec3(0.0, 0.0, 0.0),
+            material: &self.material,
+            front_face: false,
+            tangent: None,
+        };
+        rec.set_face_normal(ray, &unit_vector(outward_normal));
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let extent = vec3(self.radius, self.radius, self.radius);
+        Some(surrounding_box(
+            &aabb(self.a - extent, self.a + extent),
+            &aabb(self.b - extent, self.b + extent),
+        ))
+    }
+}
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use crate::material::Material;
+use crate::math::ray::Ray;
+use crate::math::roots::solve_quadratic;
+use crate::math::vec3::{unit_vector, vec3, Point3, Vec3, Vector};
+
+use super::aabb::{aabb, Aabb};
+use super::cylinder::azimuth_u;
+use super::{HitRecord, Hittable};
+
+/// Cone with its base of `radius` on `center` and its tip `height` above it
+/// along +y. UVs follow `Cylinder`'s.
+pub struct Cone {
+    pub center: Point3,
+    pub radius: f64,
+    pub height: f64,
+    /// Whether the base is closed by a disk.
+    pub capped: bool,
+    pub material: Arc<dyn Material>,
+}
+
+pub fn cone(
+    center: Point3,
+    radius: f64,
+    height: f64,
+    capped: bool,
+    material: Arc<dyn Material>,
+) -> Cone {
+    Cone {
+        center,
+        radius,
+        height,
+        capped,
+        material,
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let o = ray.origin() - self.center;
+        let d = ray.direction();
+
+        let mut nearest: Option<(f64, Vec3, f64, f64)> = None;
+        let mut consider = |t: f64, normal: Vec3, u: f64, v: f64| {
+            if t >= t_min && t <= t_max && nearest.is_none_or(|(best, ..)| t < best) {
+                nearest = Some((t, normal, u, v));
+            }
+        };
+
+        // x² + z² = k² (height - y)², measured from the tip down.
+        let k = self.radius / self.height;
+        let k2 = k * k;
+        let below_tip = self.height - o.y();
+        let roots = solve_quadratic(
+            d.x() * d.x() + d.z() * d.z() - k2 * d.y() * d.y(),
+            2.0 * (o.x() * d.x() + o.z() * d.z() + k2 * below_tip * d.y()),
+            o.x() * o.x() + o.z() * o.z() - k2 * below_tip * below_tip,
+        );
+        for t in roots {
+            let p = o + t * d;
+            if (0.0..=self.height).contains(&p.y()) {
+                let normal = unit_vector(vec3(p.x(), k2 * (self.height - p.y()), p.z()));
+                consider(t, normal, azimuth_u(p.x(), p.z()), p.y() / self.height);
+            }
+        }
+
+        if self.capped && d.y() != 0.0 {
+            let t = -o.y() / d.y();
+            let p = o + t * d;
+            let r = (p.x() * p.x() + p.z() * p.z()).sqrt();
+            if r <= self.radius {
+                let normal = vec3(0.0, -1.0, 0.0);
+                consider(t, normal, azimuth_u(p.x(), p.z()), r / self.radius);
+            }
+        }
+
+        let (t, outward_normal, u, v) = nearest?;
+        let mut rec = HitRecord {
+            p: ray.at(t),
+            t,
+            u,
+            v,
+            normal: vec3(0.0, 0.0, 0.0),
+            material: &self.material,
+            front_face: false,
+            tangent: None,
+        };
+        rec.set_face_normal(ray, &outward_normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let extent = vec3(self.radius, 0.0, self.radius);
+        Some(aabb(
+            self.center - extent,
+            self.center + extent + vec3(0.0, self.height, 0.0),
+        ))
+    }
+}
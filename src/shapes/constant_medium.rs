@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use crate::material::{Material, MediumBoundary};
+use crate::math::ray::Ray;
+use crate::medium::HomogeneousMedium;
+
+use super::aabb::Aabb;
+use super::{HitRecord, Hittable};
+
+/// Fills a closed boundary shape with a homogeneous medium such as fog or smoke.
+/// The boundary itself is invisible: rays entering it are handed to the
+/// medium random walk in `ray_color`, which samples free-flight distances
+/// until the ray scatters or leaves through the boundary again.
+pub struct ConstantMedium {
+    pub boundary: Arc<dyn Hittable>,
+    pub boundary_material: Arc<dyn Material>,
+}
+
+pub fn constant_medium(boundary: Arc<dyn Hittable>, medium: HomogeneousMedium) -> ConstantMedium {
+    ConstantMedium {
+        boundary,
+        boundary_material: Arc::new(MediumBoundary {
+            medium: Arc::new(medium),
+        }),
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut rec = self.boundary.hit(ray, t_min, t_max)?;
+        rec.material = &self.boundary_material;
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.boundary.bounding_box()
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use crate::math::constants::INFINITY;
+use crate::math::ray::Ray;
+use crate::math::vec3::{vec3, Vector};
+
+use super::aabb::{aabb, surrounding_box, Aabb};
+use super::{HitRecord, Hittable};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    /// The left shape with the right one carved out of it.
+    Difference,
+}
+
+impl CsgOperation {
+    fn inside(self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => in_left || in_right,
+            CsgOperation::Intersection => in_left && in_right,
+            CsgOperation::Difference => in_left && !in_right,
+        }
+    }
+}
+
+/// Boolean combination of two closed shapes. Surfaces keep the material of
+/// the shape they came from, so a hole carved by a difference is lined with
+/// the right shape's material. Nodes can be nested.
+pub struct Csg {
+    pub left: Arc<dyn Hittable>,
+    pub right: Arc<dyn Hittable>,
+    pub operation: CsgOperation,
+}
+
+pub fn csg_union(left: Arc<dyn Hittable>, right: Arc<dyn Hittable>) -> Csg {
+    Csg {
+        left,
+        right,
+        operation: CsgOperation::Union,
+    }
+}
+
+pub fn csg_intersection(left: Arc<dyn Hittable>, right: Arc<dyn Hittable>) -> Csg {
+    Csg {
+        left,
+        right,
+        operation: CsgOperation::Intersection,
+    }
+}
+
+pub fn csg_difference(left: Arc<dyn Hittable>, right: Arc<dyn Hittable>) -> Csg {
+    Csg {
+        left,
+        right,
+        operation: CsgOperation::Difference,
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.hit_all(ray, t_min, t_max).into_iter().next()
+    }
+
+    /// Merges the crossings of both shapes along the ray and keeps those
+    /// where being inside the combination changes.
+    fn hit_all(&self, ray: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord<'_>> {
+        // Both lists run to infinity: whether the ray starts inside a shape
+        // is read off its first crossing being an exit.
+        let left = self.left.hit_all(ray, t_min, INFINITY);
+        let right = self.right.hit_all(ray, t_min, INFINITY);
+        let mut in_left = left.first().is_some_and(|rec| !rec.front_face);
+        let mut in_right = right.first().is_some_and(|rec| !rec.front_face);
+        let mut inside = self.operation.inside(in_left, in_right);
+
+        let mut crossings: Vec<(HitRecord, bool)> = left
+            .into_iter()
+            .map(|rec| (rec, true))
+            .chain(right.into_iter().map(|rec| (rec, false)))
+            .collect();
+        crossings.sort_by(|(a, _), (b, _)| a.t.total_cmp(&b.t));
+
+        let mut hits = Vec::new();
+        for (mut rec, from_left) in crossings {
+            if rec.t > t_max {
+                break;
+            }
+            if from_left {
+                in_left = rec.front_face;
+            } else {
+                in_right = rec.front_face;
+            }
+            let now_inside = self.operation.inside(in_left, in_right);
+            if now_inside != inside {
+                // The normal already faces against the ray; only which side
+                // counts as outside can change.
+                rec.front_face = now_inside;
+                inside = now_inside;
+                hits.push(rec);
+            }
+        }
+        hits
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        match self.operation {
+            CsgOperation::Union => Some(surrounding_box(
+                &self.left.bounding_box()?,
+                &self.right.bounding_box()?,
+            )),
+            CsgOperation::Intersection => {
+                match (self.left.bounding_box(), self.right.bounding_box()) {
+                    (Some(a), Some(b)) => Some(overlap(&a, &b)),
+                    (a, b) => a.or(b),
+                }
+            }
+            CsgOperation::Difference => self.left.bounding_box(),
+        }
+    }
+}
+
+fn overlap(a: &Aabb, b: &Aabb) -> Aabb {
+    aabb(
+        vec3(
+            a.minimum.x().max(b.minimum.x()),
+            a.minimum.y().max(b.minimum.y()),
+            a.minimum.z().max(b.minimum.z()),
+        ),
+        vec3(
+            a.maximum.x().min(b.maximum.x()),
+            a.maximum.y().min(b.maximum.y()),
+            a.maximum.z().min(b.maximum.z()),
+        ),
+    )
+}
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use crate::material::Material;
+use crate::math::ray::Ray;
+use crate::math::vec3::{vec3, Point3, Vector};
+
+use super::aabb::{aabb, Aabb};
+use super::{HitRecord, Hittable};
+
+/// Axis-aligned box, intersected with a slab test. Each face gets its own
+/// (u, v) square running along the two axes it spans.
+pub struct Cuboid {
+    pub bounds: Aabb,
+    pub material: Arc<dyn Material>,
+}
+
+/// Box between two opposite corners, given in any order.
+pub fn cuboid(a: Point3, b: Point3, material: Arc<dyn Material>) -> Cuboid {
+    Cuboid {
+        bounds: aabb(
+            vec3(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z())),
+            vec3(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z())),
+        ),
+        material,
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (t_enter, t_exit) = self.bounds.hit(ray, t_min, t_max)?;
+        // The entry is clipped to t_min when the ray starts inside the box.
+        let t = if t_enter > t_min { t_enter } else { t_exit };
+        if t >= t_max {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let outward_normal = self.bounds.outward_normal(&p);
+        let local = (p - self.bounds.minimum) / (self.bounds.maximum - self.bounds.minimum);
+        let (u, v) = if outward_normal.x() != 0.0 {
+            (local.z(), local.y())
+        } else if outward_normal.y() != 0.0 {
+            (local.x(), local.z())
+        } else {
+            (local.x(), local.y())
+        };
+
+        let mut rec = HitRecord {
+            p,
+            t,
+            u,
+            v,
+            normal: vec3(0.0, 0.0, 0.0),
+            material: &self.material,
+            front_face: false,
+            tangent: None,
+        };
+        rec.set_face_normal(ray, &outward_normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
@@ -0,0 +1,279 @@
+use std::sync::Arc;
+
+use crate::material::Material;
+use crate::math::onb::Onb;
+use crate::math::ray::Ray;
+use crate::math::vec3::{dot, lerp, unit_vector, vec3, Point3, Vec3, Vector};
+
+use super::aabb::{aabb, surrounding_box, Aabb};
+use super::{HitRecord, Hittable};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CurveMode {
+    /// Flat strip that always faces the ray. Cheapest, and good enough for
+    /// fibres that are only a few pixels wide.
+    Ribbon,
+    /// Flat strip whose normal turns from `normal0` at the start to
+    /// `normal1` at the end, e.g. a blade of grass. Seen edge on it vanishes.
+    OrientedRibbon { normal0: Vec3, normal1: Vec3 },
+    /// Round tube, with the width as its diameter. Only the side facing the
+    /// ray is found, so rays leaving the tube's inside pass out of it; the
+    /// hair model accounts for the way through the fibre itself.
+    Tube,
+}
+
+/// Cubic Bézier curve whose width varies linearly from `width0` to
+/// `width1`. u runs along the curve and v across it, and hits report the
+/// curve's direction as their tangent for hair shading.
+///
+/// Curves are intersected in a space where the ray runs down the z axis:
+/// the curve is split in halves until the pieces are nearly straight,
+/// pruning any whose box misses the ray, and each piece is then treated as
+/// a segment.
+pub struct Curve {
+    pub points: [Point3; 4],
+    pub width0: f64,
+    pub width1: f64,
+    pub mode: CurveMode,
+    pub material: Arc<dyn Material>,
+    /// How many times the curve is halved before pieces count as straight.
+    max_depth: u32,
+}
+
+pub fn curve(
+    points: [Point3; 4],
+    width0: f64,
+    width1: f64,
+    mode: CurveMode,
+    material: Arc<dyn Material>,
+) -> Curve {
+    // Enough splits to keep the pieces within a fraction of the width of
+    // the real curve, from the bound on the control polygon's bending.
+    let bend = (0..2)
+        .map(|i| {
+            let d = points[i] - 2.0 * points[i + 1] + points[i + 2];
+            d.x().abs().max(d.y().abs()).max(d.z().abs())
+        })
+        .fold(0.0, f64::max);
+    let tolerance = 0.05 * width0.max(width1);
+    let max_depth = if bend > 0.0 && tolerance > 0.0 {
+        ((2.0f64.sqrt() * 6.0 * bend / (8.0 * tolerance)).log2() / 2.0)
+            .ceil()
+            .clamp(0.0, 10.0) as u32
+    } else {
+        0
+    };
+
+    Curve {
+        points,
+        width0,
+        width1,
+        mode,
+        material,
+        max_depth,
+    }
+}
+
+fn bezier(p: &[Vec3; 4], u: f64) -> Vec3 {
+    let s = 1.0 - u;
+    s * s * s * p[0] + 3.0 * s * s * u * p[1] + 3.0 * s * u * u * p[2] + u * u * u * p[3]
+}
+
+fn bezier_derivative(p: &[Vec3; 4], u: f64) -> Vec3 {
+    let s = 1.0 - u;
+    3.0 * (s * s * (p[1] - p[0]) + 2.0 * s * u * (p[2] - p[1]) + u * u * (p[3] - p[2]))
+}
+
+/// Splits a Bézier curve at its middle (de Casteljau).
+fn subdivide(p: &[Vec3; 4]) -> ([Vec3; 4], [Vec3; 4]) {
+    let p01 = 0.5 * (p[0] + p[1]);
+    let p12 = 0.5 * (p[1] + p[2]);
+    let p23 = 0.5 * (p[2] + p[3]);
+    let p012 = 0.5 * (p01 + p12);
+    let p123 = 0.5 * (p12 + p23);
+    let middle = 0.5 * (p012 + p123);
+    ([p[0], p01, p012, middle], [middle, p123, p23, p[3]])
+}
+
+/// A hit found in ray space: distance along the unit ray direction, and
+/// the curve's (u, v) there.
+struct CurveHit {
+    z: f64,
+    u: f64,
+    v: f64,
+}
+
+impl Curve {
+    fn width(&self, u: f64) -> f64 {
+        self.width0 + (self.width1 - self.width0) * u
+    }
+
+    fn ribbon_normal(&self, u: f64) -> Option<Vec3> {
+        match self.mode {
+            CurveMode::OrientedRibbon { normal0, normal1 } => {
+                Some(unit_vector(lerp(normal0, normal1, u)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Looks for the nearest hit with `cp`, the part of the curve between
+    /// `u0` and `u1` in ray space, closer than `z_max`.
+    #[allow(clippy::too_many_arguments)]
+    fn intersect(
+        &self,
+        cp: &[Vec3; 4],
+        u0: f64,
+        u1: f64,
+        depth: u32,
+        direction: &Vec3,
+        z_min: f64,
+        z_max: f64,
+    ) -> Option<CurveHit> {
+        let half_width = 0.5 * self.width0.max(self.width1);
+        let low = cp[1..].iter().fold(cp[0], |b, p| {
+            vec3(b.x().min(p.x()), b.y().min(p.y()), b.z().min(p.z()))
+        });
+        let high = cp[1..].iter().fold(cp[0], |b, p| {
+            vec3(b.x().max(p.x()), b.y().max(p.y()), b.z().max(p.z()))
+        });
+        if low.x() - half_width > 0.0
+            || high.x() + half_width < 0.0
+            || low.y() - half_width > 0.0
+            || high.y() + half_width < 0.0
+            || low.z() - half_width > z_max
+            || high.z() + half_width < z_min
+        {
+            return None;
+        }
+
+        if depth > 0 {
+            let (first, second) = subdivide(cp);
+            let middle = 0.5 * (u0 + u1);
+            let near = self.intersect(&first, u0, middle, depth - 1, direction, z_min, z_max);
+            let z_max = near.as_ref().map_or(z_max, |hit| hit.z);
+            let far = self.intersect(&second, middle, u1, depth - 1, direction, z_min, z_max);
+            return far.or(near);
+        }
+
+        // The ray must pass between the lines perpendicular to the piece at
+        // its two ends, so neighbouring pieces don't both claim it.
+        let start_edge = (cp[1].y() - cp[0].y()) * -cp[0].y() + cp[0].x() * (cp[0].x() - cp[1].x());
+        let end_edge = (cp[2].y() - cp[3].y()) * -cp[3].y() + cp[3].x() * (cp[3].x() - cp[2].x());
+        if start_edge < 0.0 || end_edge < 0.0 {
+            return None;
+        }
+
+        // Closest point of the piece's chord to the ray, in the xy plane.
+        let chord = cp[3] - cp[0];
+        let chord_length_squared = chord.x() * chord.x() + chord.y() * chord.y();
+        let w = if chord_length_squared > 0.0 {
+            (-(cp[0].x() * chord.x() + cp[0].y() * chord.y()) / chord_length_squared)
+                .clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let u = u0 + (u1 - u0) * w;
+        let mut hit_width = self.width(u);
+        if let Some(normal) = self.ribbon_normal(u) {
+            hit_width *= dot(&normal, direction).abs();
+        }
+
+        let on_curve = bezier(cp, w);
+        let distance_squared = on_curve.x() * on_curve.x() + on_curve.y() * on_curve.y();
+        let radius = 0.5 * hit_width;
+        if distance_squared > radius * radius {
+            return None;
+        }
+        let mut z = on_curve.z();
+        if self.mode == CurveMode::Tube {
+            // Step back from the axis to the near side of the tube.
+            z -= (radius * radius - distance_squared).sqrt();
+        }
+        if z < z_min || z > z_max {
+            return None;
+        }
+
+        // Which side of the curve the ray passed, for v.
+        let tangent = bezier_derivative(cp, w);
+        let side = tangent.x() * -on_curve.y() + tangent.y() * on_curve.x();
+        let offset = distance_squared.sqrt() * side.signum();
+        Some(CurveHit {
+            z,
+            u,
+            v: 0.5 + offset / hit_width,
+        })
+    }
+}
+
+impl Hittable for Curve {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let length = ray.direction().length();
+        let direction = ray.direction() / length;
+        let frame = Onb::build_from_w(&direction);
+        let origin = ray.origin();
+        let to_ray_space = |p: Point3| {
+            let offset = p - origin;
+            vec3(
+                dot(&offset, &frame.u),
+                dot(&offset, &frame.v),
+                dot(&offset, &frame.w),
+            )
+        };
+        let cp = self.points.map(to_ray_space);
+        let hit = self.intersect(
+            &cp,
+            0.0,
+            1.0,
+            self.max_depth,
+            &direction,
+            t_min * length,
+            t_max * length,
+        )?;
+
+        let t = hit.z / length;
+        let p = ray.at(t);
+        let tangent = unit_vector(bezier_derivative(&self.points, hit.u));
+        // The flat modes face the ray, with the normal turned to lie across
+        // the curve.
+        let facing = -direction + dot(&direction, &tangent) * tangent;
+        let outward_normal = match self.mode {
+            CurveMode::Ribbon => facing,
+            CurveMode::OrientedRibbon { .. } => self.ribbon_normal(hit.u).unwrap(),
+            CurveMode::Tube => {
+                let axis = bezier(&self.points, hit.u);
+                let radial = p - axis - dot(&(p - axis), &tangent) * tangent;
+                if radial.length_squared() > 0.0 {
+                    radial
+                } else {
+                    facing
+                }
+            }
+        };
+
+        let mut rec = HitRecord {
+            p,
+            t,
+            u: hit.u,
+            v: hit.v,
+            normal: vec3(0.0, 0.0, 0.0),
+            material: &self.material,
+            front_face: false,
+            tangent: Some(tangent),
+        };
+        rec.set_face_normal(ray, &unit_vector(outward_normal));
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let half_width = 0.5 * self.width0.max(self.width1);
+        let pad = vec3(half_width, half_width, half_width);
+        Some(
+            self.points[1..]
+                .iter()
+                .fold(aabb(self.points[0] - pad, self.points[0] + pad), |b, p| {
+                    surrounding_box(&b, &aabb(*p - pad, *p + pad))
+                }),
+        )
+    }
+}
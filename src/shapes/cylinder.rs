@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use crate::material::Material;
+use crate::math::constants::PI;
+use crate::math::ray::Ray;
+use crate::math::roots::solve_quadratic;
+use crate::math::vec3::{vec3, Point3, Vec3, Vector};
+
+use super::aabb::{aabb, Aabb};
+use super::{HitRecord, Hittable};
+
+/// Cylinder standing on `center` and rising `height` along +y. Use
+/// `transformed` to point it elsewhere. On the side u goes around the axis
+/// and v runs up it; on the caps u goes around and v runs out to the rim.
+pub struct Cylinder {
+    pub center: Point3,
+    pub radius: f64,
+    pub height: f64,
+    /// Whether the ends are closed by disks.
+    pub capped: bool,
+    pub material: Arc<dyn Material>,
+}
+
+pub fn cylinder(
+    center: Point3,
+    radius: f64,
+    height: f64,
+    capped: bool,
+    material: Arc<dyn Material>,
+) -> Cylinder {
+    Cylinder {
+        center,
+        radius,
+        height,
+        capped,
+        material,
+    }
+}
+
+/// Angle around the y axis mapped to [0, 1].
+pub fn azimuth_u(x: f64, z: f64) -> f64 {
+    ((-z).atan2(x) + PI) / (2.0 * PI)
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let o = ray.origin() - self.center;
+        let d = ray.direction();
+
+        // Candidate hits as (t, outward normal, u, v); the nearest in range wins.
+        let mut nearest: Option<(f64, Vec3, f64, f64)> = None;
+        let mut consider = |t: f64, normal: Vec3, u: f64, v: f64| {
+            if t >= t_min && t <= t_max && nearest.is_none_or(|(best, ..)| t < best) {
+                nearest = Some((t, normal, u, v));
+            }
+        };
+
+        let roots = solve_quadratic(
+            d.x() * d.x() + d.z() * d.z(),
+            2.0 * (o.x() * d.x() + o.z() * d.z()),
+            o.x() * o.x() + o.z() * o.z() - self.radius * self.radius,
+        );
+        for t in roots {
+            let p = o + t * d;
+            if (0.0..=self.height).contains(&p.y()) {
+                let normal = vec3(p.x(), 0.0, p.z()) / self.radius;
+                consider(t, normal, azimuth_u(p.x(), p.z()), p.y() / self.height);
+            }
+        }
+
+        if self.capped && d.y() != 0.0 {
+            for (y, normal_y) in [(0.0, -1.0), (self.height, 1.0)] {
+                let t = (y - o.y()) / d.y();
+                let p = o + t * d;
+                let r = (p.x() * p.x() + p.z() * p.z()).sqrt();
+                if r <= self.radius {
+                    let normal = vec3(0.0, normal_y, 0.0);
+                    consider(t, normal, azimuth_u(p.x(), p.z()), r / self.radius);
+                }
+            }
+        }
+
+        let (t, outward_normal, u, v) = nearest?;
+        let mut rec = HitRecord {
+            p: ray.at(t),
+            t,
+            u,
+            v,
+            normal: vec3(0.0, 0.0, 0.0),
+            material: &self.material,
+            front_face: false,
+            tangent: None,
+        };
+        rec.set_face_normal(ray, &outward_normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let extent = vec3(self.radius, 0.0, self.radius);
+        Some(aabb(
+            self.center - extent,
+            self.center + extent + vec3(0.0, self.height, 0.0),
+        ))
+    }
+}
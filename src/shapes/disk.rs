@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use crate::material::Material;
+use crate::math::constants::PI;
+use crate::math::onb::Onb;
+use crate::math::ray::Ray;
+use crate::math::vec3::{dot, unit_vector, vec3, Point3, Vec3, Vector};
+
+use super::aabb::{aabb, pad, Aabb};
+use super::{HitRecord, Hittable};
+
+/// Flat disk facing `normal`. u goes once around the rim and v runs from
+/// the center (0) to the rim (1).
+pub struct Disk {
+    pub center: Point3,
+    pub normal: Vec3,
+    pub radius: f64,
+    pub material: Arc<dyn Material>,
+    axes: Onb,
+}
+
+pub fn disk(center: Point3, normal: Vec3, radius: f64, material: Arc<dyn Material>) -> Disk {
+    let normal = unit_vector(normal);
+    Disk {
+        center,
+        normal,
+        radius,
+        material,
+        axes: Onb::build_from_w(&normal),
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let denom = dot(&self.normal, &ray.direction());
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+        let t = dot(&self.normal, &(self.center - ray.origin())) / denom;
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let planar = p - self.center;
+        let distance_squared = planar.length_squared();
+        if distance_squared > self.radius * self.radius {
+            return None;
+        }
+
+        let phi = dot(&planar, &self.axes.v).atan2(dot(&planar, &self.axes.u));
+        let mut rec = HitRecord {
+            p,
+            t,
+            u: (phi + PI) / (2.0 * PI),
+            v: distance_squared.sqrt() / self.radius,
+            normal: vec3(0.0, 0.0, 0.0),
+            material: &self.material,
+            front_face: false,
+            tangent: None,
+        };
+        rec.set_face_normal(ray, &self.normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        // How far the rim reaches along each axis.
+        let n = self.normal;
+        let extent = self.radius
+            * vec3(
+                (1.0 - n.x() * n.x()).max(0.0).sqrt(),
+                (1.0 - n.y() * n.y()).max(0.0).sqrt(),
+                (1.0 - n.z() * n.z()).max(0.0).sqrt(),
+            );
+        Some(pad(aabb(self.center - extent, self.center + extent)))
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use crate::camera::Camera;
+use crate::math::vec3::{luminance, unit_vector, Vec3, Vector};
+use crate::texture::Texture;
+
+use super::subdivision::vertex_normals;
+use super::tri_mesh::MeshData;
+
+// Displacement mapping as a pre-pass over a mesh: triangles are split until
+// they are small on screen, then every vertex is pushed along its normal by
+// a height read from a texture. The result goes to `tri_mesh` as usual.
+
+/// How finely `displace` tessellates: edges are split until they span at
+/// most `max_edge` pixels of an image `image_width` pixels wide seen through
+/// `camera`, but never more than `max_levels` times over. Edges behind the
+/// camera are left alone.
+pub struct ScreenTessellation<'a> {
+    pub camera: &'a Camera,
+    pub image_width: i32,
+    pub max_edge: f64,
+    pub max_levels: usize,
+}
+
+/// Tessellates `mesh` and moves each vertex along its normal by `scale` times
+/// the luminance of `height` at the vertex. Texture coordinates are
+/// interpolated (or zero if the mesh has none) and the result gets new vertex
+/// normals. Use a negative `scale` to carve into the surface.
+pub fn displace(
+    mesh: &MeshData,
+    height: &dyn Texture,
+    scale: f64,
+    tessellation: &ScreenTessellation,
+) -> MeshData {
+    let normals = match &mesh.normals {
+        Some(normals) => normals.clone(),
+        None => vertex_normals(&mesh.positions, &mesh.indices),
+    };
+    let mut vertices: Vec<Vertex> = (0..mesh.positions.len())
+        .map(|i| Vertex {
+            position: mesh.positions[i],
+            normal: normals[i],
+            uv: mesh.uvs.as_ref().map_or((0.0, 0.0), |uvs| uvs[i]),
+        })
+        .collect();
+
+    let mut indices = mesh.indices.clone();
+    for _ in 0..tessellation.max_levels {
+        match refine(&mut vertices, &indices, tessellation) {
+            Some(refined) => indices = refined,
+            None => break,
+        }
+    }
+
+    let positions: Vec<Vec3> = vertices
+        .iter()
+        .map(|vertex| {
+            let h = luminance(height.value(vertex.uv.0, vertex.uv.1, &vertex.position));
+            vertex.position + scale * h * vertex.normal
+        })
+        .collect();
+    let normals = vertex_normals(&positions, &indices);
+    MeshData {
+        positions,
+        indices,
+        normals: Some(normals),
+        uvs: mesh
+            .uvs
+            .as_ref()
+            .map(|_| vertices.iter().map(|vertex| vertex.uv).collect()),
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Vertex {
+    position: Vec3,
+    normal: Vec3,
+    uv: (f64, f64),
+}
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+/// Length of the edge from `a` to `b` in pixels, if both ends are in front
+/// of the camera.
+fn screen_length(a: &Vec3, b: &Vec3, tessellation: &ScreenTessellation) -> Option<f64> {
+    let camera = tessellation.camera;
+    let (sa, ta) = camera.project(a)?;
+    let (sb, tb) = camera.project(b)?;
+    let width = tessellation.image_width as f64;
+    let height = width / camera.aspect_ratio();
+    Some(((sb - sa) * width).hypot((tb - ta) * height))
+}
+
+/// Splits every edge that is too long on screen at its midpoint, adding the
+/// midpoints to `vertices`. Triangles are cut into two, three or four
+/// depending on how many of their edges were split, and neighbours share the
+/// midpoints, so no cracks open up. Returns `None` if nothing was split.
+fn refine(
+    vertices: &mut Vec<Vertex>,
+    indices: &[[usize; 3]],
+    tessellation: &ScreenTessellation,
+) -> Option<Vec<[usize; 3]>> {
+    let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+    for &[a, b, c] in indices {
+        for (from, to) in [(a, b), (b, c), (c, a)] {
+            let key = edge_key(from, to);
+            if midpoints.contains_key(&key) {
+                continue;
+            }
+            let (p, q) = (vertices[key.0], vertices[key.1]);
+            let too_long = screen_length(&p.position, &q.position, tessellation)
+                .is_some_and(|length| length > tessellation.max_edge);
+            if too_long {
+                let normal = p.normal + q.normal;
+                midpoints.insert(key, vertices.len());
+                vertices.push(Vertex {
+                    position: 0.5 * (p.position + q.position),
+                    normal: if normal.length_squared() > 0.0 {
+                        unit_vector(normal)
+                    } else {
+                        p.normal
+                    },
+                    uv: (0.5 * (p.uv.0 + q.uv.0), 0.5 * (p.uv.1 + q.uv.1)),
+                });
+            }
+        }
+    }
+    if midpoints.is_empty() {
+        return None;
+    }
+
+    let mut refined = Vec::with_capacity(2 * indices.len());
+    for triangle in indices {
+        let split = |i: usize| midpoints.get(&edge_key(triangle[i], triangle[(i + 1) % 3]));
+        let count = (0..3).filter(|&i| split(i).is_some()).count();
+        // Rotate the corners so the split edges come first.
+        let first = match count {
+            1 => (0..3).find(|&i| split(i).is_some()).unwrap_or(0),
+            2 => (0..3).find(|&i| split((i + 2) % 3).is_none()).unwrap_or(0),
+            _ => 0,
+        };
+        let [a, b, c] = [
+            triangle[first],
+            triangle[(first + 1) % 3],
+            triangle[(first + 2) % 3],
+        ];
+        let ab = split(first).copied();
+        let bc = split((first + 1) % 3).copied();
+        let ca = split((first + 2) % 3).copied();
+        match (ab, bc, ca) {
+            (Some(ab), Some(bc), Some(ca)) => {
+                refined.push([a, ab, ca]);
+                refined.push([ab, b, bc]);
+                refined.push([ca, bc, c]);
+                refined.push([ab, bc, ca]);
+            }
+            (Some(ab), Some(bc), None) => {
+                refined.push([ab, b, bc]);
+                refined.push([a, ab, bc]);
+                refined.push([a, bc, c]);
+            }
+            (Some(ab), None, None) => {
+                refined.push([a, ab, c]);
+                refined.push([ab, b, c]);
+            }
+            _ => refined.push([a, b, c]),
+        }
+    }
+    Some(refined)
+}
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use crate::material::{Material, MediumBoundary};
+use crate::math::ray::Ray;
+use crate::math::vec3::vec3;
+use crate::medium::GridMedium;
+
+use super::aabb::Aabb;
+use super::{HitRecord, Hittable};
+
+/// The box enclosing a `GridMedium`. Like `ConstantMedium` its surface is
+/// invisible and only hands rays over to the medium.
+pub struct GridVolume {
+    pub bounds: Aabb,
+    pub boundary_material: Arc<dyn Material>,
+}
+
+pub fn grid_volume(medium: GridMedium) -> GridVolume {
+    GridVolume {
+        bounds: medium.bounds,
+        boundary_material: Arc::new(MediumBoundary {
+            medium: Arc::new(medium),
+        }),
+    }
+}
+
+impl Hittable for GridVolume {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (t_enter, t_exit) = self.bounds.hit(ray, t_min, t_max)?;
+        // The entry is clipped to t_min when the ray starts inside the box.
+        let t = if t_enter > t_min { t_enter } else { t_exit };
+        if t >= t_max {
+            return None;
+        }
+
+        let mut rec = HitRecord {
+            p: ray.at(t),
+            t,
+            u: 0.0,
+            v: 0.0,
+            normal: vec3(0.0, 0.0, 0.0),
+            material: &self.boundary_material,
+            front_face: false,
+            tangent: None,
+        };
+        let outward_normal = self.bounds.outward_normal(&rec.p);
+        rec.set_face_normal(ray, &outward_normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
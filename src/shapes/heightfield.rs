@@ -0,0 +1,267 @@
+use std::sync::Arc;
+
+use crate::load::HeightGrid;
+use crate::material::Material;
+use crate::math::constants::INFINITY;
+use crate::math::ray::Ray;
+use crate::math::vec3::{cross, dot, unit_vector, vec3, Point3, Vec3, Vector};
+
+use super::aabb::{aabb, Aabb};
+use super::tri_mesh::intersect_triangle;
+use super::{HitRecord, Hittable};
+
+/// Terrain from a grid of heights. Each grid cell holds two triangles, and
+/// rays walk the cells they cross in order (a 2D DDA), skipping cells whose
+/// height range they pass over or under. Normals are interpolated from the
+/// grid's slopes, and (u, v) spans the whole field along x and z.
+pub struct Heightfield {
+    pub grid: HeightGrid,
+    /// Lowest corner: the field covers x and z from here, and a height of
+    /// 0 sits at this y.
+    pub corner: Point3,
+    /// Extent along x and z, and the height a grid value of 1 rises to.
+    pub size: Vec3,
+    pub material: Arc<dyn Material>,
+    /// Lowest and highest grid value around each cell.
+    cell_ranges: Vec<(f32, f32)>,
+    bounds: Aabb,
+}
+
+/// Spreads `grid` over `size.x` by `size.z` from `corner`, scaling heights
+/// by `size.y`, or `None` if the grid has fewer than 2 samples along an
+/// axis.
+pub fn heightfield(
+    grid: HeightGrid,
+    corner: Point3,
+    size: Vec3,
+    material: Arc<dyn Material>,
+) -> Option<Heightfield> {
+    if grid.nx < 2 || grid.nz < 2 {
+        return None;
+    }
+    let mut cell_ranges = Vec::with_capacity((grid.nx - 1) * (grid.nz - 1));
+    for k in 0..grid.nz - 1 {
+        for i in 0..grid.nx - 1 {
+            let corners = [
+                grid.value(i, k),
+                grid.value(i + 1, k),
+                grid.value(i, k + 1),
+                grid.value(i + 1, k + 1),
+            ];
+            let low = corners.iter().cloned().fold(f32::INFINITY, f32::min);
+            let high = corners.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+            cell_ranges.push((low, high));
+        }
+    }
+    let low = grid.values.iter().cloned().fold(f32::INFINITY, f32::min) as f64;
+    let high = grid
+        .values
+        .iter()
+        .cloned()
+        .fold(f32::NEG_INFINITY, f32::max) as f64;
+    // Padded so a flat field still has some thickness.
+    let bounds = aabb(
+        corner + vec3(0.0, low * size.y() - 1e-4, 0.0),
+        corner + vec3(size.x(), high * size.y() + 1e-4, size.z()),
+    );
+
+    Some(Heightfield {
+        grid,
+        corner,
+        size,
+        material,
+        cell_ranges,
+        bounds,
+    })
+}
+
+impl Heightfield {
+    fn cell_size(&self) -> (f64, f64) {
+        (
+            self.size.x() / (self.grid.nx - 1) as f64,
+            self.size.z() / (self.grid.nz - 1) as f64,
+        )
+    }
+
+    fn vertex(&self, i: usize, k: usize) -> Point3 {
+        let (cx, cz) = self.cell_size();
+        self.corner
+            + vec3(
+                i as f64 * cx,
+                self.grid.value(i, k) as f64 * self.size.y(),
+                k as f64 * cz,
+            )
+    }
+
+    /// Normal at a grid point from central differences, one-sided on the edges.
+    fn vertex_normal(&self, i: usize, k: usize) -> Vec3 {
+        let (cx, cz) = self.cell_size();
+        let height = |i: usize, k: usize| self.grid.value(i, k) as f64 * self.size.y();
+        let (i0, i1) = (i.saturating_sub(1), (i + 1).min(self.grid.nx - 1));
+        let (k0, k1) = (k.saturating_sub(1), (k + 1).min(self.grid.nz - 1));
+        let slope_x = (height(i1, k) - height(i0, k)) / ((i1 - i0) as f64 * cx);
+        let slope_z = (height(i, k1) - height(i, k0)) / ((k1 - k0) as f64 * cz);
+        unit_vector(vec3(-slope_x, 1.0, -slope_z))
+    }
+
+    /// Tests the two triangles of cell (i, k).
+    fn hit_cell(
+        &self,
+        i: usize,
+        k: usize,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<HitRecord<'_>> {
+        let corners = [(i, k), (i + 1, k), (i + 1, k + 1), (i, k + 1)];
+        let triangles = [[0, 1, 2], [0, 2, 3]];
+        let points = |triangle: usize| triangles[triangle].map(|c| corners[c]);
+        let edges = |triangle: usize| {
+            let [p0, p1, p2] = points(triangle).map(|(i, k)| self.vertex(i, k));
+            (p0, p1 - p0, p2 - p0)
+        };
+
+        let mut nearest: Option<(f64, usize, f64, f64)> = None;
+        for triangle in 0..2 {
+            let (p0, edge1, edge2) = edges(triangle);
+            let t_limit = nearest.map_or(t_max, |(t, ..)| t);
+            if let Some((t, b1, b2)) = intersect_triangle(ray, &p0, &edge1, &edge2, t_min, t_limit)
+            {
+                nearest = Some((t, triangle, b1, b2));
+            }
+        }
+        let (t, triangle, b1, b2) = nearest?;
+        let (_, edge1, edge2) = edges(triangle);
+        let geometric_normal = cross(&edge1, &edge2);
+        let weights = [1.0 - b1 - b2, b1, b2];
+        let grid_points = points(triangle);
+
+        let p = ray.at(t);
+        let mut rec = HitRecord {
+            p,
+            t,
+            u: (p.x() - self.corner.x()) / self.size.x(),
+            v: (p.z() - self.corner.z()) / self.size.z(),
+            normal: vec3(0.0, 0.0, 0.0),
+            material: &self.material,
+            front_face: false,
+            tangent: None,
+        };
+        // Facing up is outside, whatever the winding.
+        let geometric_normal = if geometric_normal.y() < 0.0 {
+            -geometric_normal
+        } else {
+            geometric_normal
+        };
+        rec.set_face_normal(ray, &unit_vector(geometric_normal));
+        let shading_normal = unit_vector(
+            grid_points
+                .iter()
+                .zip(weights.iter())
+                .fold(vec3(0.0, 0.0, 0.0), |sum, (&(i, k), &w)| {
+                    sum + w * self.vertex_normal(i, k)
+                }),
+        );
+        rec.normal = if dot(&shading_normal, &rec.normal) < 0.0 {
+            -shading_normal
+        } else {
+            shading_normal
+        };
+        Some(rec)
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (t_enter, t_exit) = self.bounds.hit(ray, t_min, t_max)?;
+        let (cx, cz) = self.cell_size();
+        let (last_i, last_k) = (self.grid.nx - 2, self.grid.nz - 2);
+        let o = ray.origin();
+        let d = ray.direction();
+
+        let start = ray.at(t_enter);
+        let cell = |offset: f64, cell_size: f64, last: usize| {
+            ((offset / cell_size).floor().max(0.0) as usize).min(last)
+        };
+        let mut i = cell(start.x() - self.corner.x(), cx, last_i);
+        let mut k = cell(start.z() - self.corner.z(), cz, last_k);
+
+        // Parameter at which the ray crosses the next cell boundary on each
+        // axis, and how far apart those crossings are.
+        let boundary = |origin: f64, direction: f64, corner: f64, index: usize, size: f64| {
+            if direction > 0.0 {
+                (corner + (index + 1) as f64 * size - origin) / direction
+            } else if direction < 0.0 {
+                (corner + index as f64 * size - origin) / direction
+            } else {
+                INFINITY
+            }
+        };
+        let mut next_x = boundary(o.x(), d.x(), self.corner.x(), i, cx);
+        let mut next_z = boundary(o.z(), d.z(), self.corner.z(), k, cz);
+        let delta_x = if d.x() != 0.0 {
+            cx / d.x().abs()
+        } else {
+            INFINITY
+        };
+        let delta_z = if d.z() != 0.0 {
+            cz / d.z().abs()
+        } else {
+            INFINITY
+        };
+
+        let mut cell_enter = t_enter;
+        loop {
+            let cell_exit = next_x.min(next_z).min(t_exit);
+
+            // Skip the cell if the ray stays above or below all of it.
+            let (low, high) = self.cell_ranges[k * (self.grid.nx - 1) + i];
+            let y_enter = ray.at(cell_enter).y();
+            let y_exit = ray.at(cell_exit).y();
+            let low = self.corner.y() + low as f64 * self.size.y();
+            let high = self.corner.y() + high as f64 * self.size.y();
+            let passes = (y_enter > high && y_exit > high) || (y_enter < low && y_exit < low);
+            if !passes {
+                if let Some(rec) = self.hit_cell(i, k, ray, t_min, t_max) {
+                    return Some(rec);
+                }
+            }
+
+            if cell_exit >= t_exit {
+                return None;
+            }
+            cell_enter = cell_exit;
+            if next_x < next_z {
+                if d.x() > 0.0 {
+                    if i == last_i {
+                        return None;
+                    }
+                    i += 1;
+                } else {
+                    if i == 0 {
+                        return None;
+                    }
+                    i -= 1;
+                }
+                next_x += delta_x;
+            } else {
+                if d.z() > 0.0 {
+                    if k == last_k {
+                        return None;
+                    }
+                    k += 1;
+                } else {
+                    if k == 0 {
+                        return None;
+                    }
+                    k -= 1;
+                }
+                next_z += delta_z;
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
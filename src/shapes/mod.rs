@@ -1,19 +1,25 @@
 use std::sync::Arc;
 
 use crate::material::Material;
-use crate::math::vec3::{dot, Point3, Vec3};
+use crate::math::vec3::{dot, Point3, Vec3, Vector};
+use crate::medium::Medium;
 use crate::Ray;
 pub struct HitRecord<'a> {
     pub p: Point3,
     pub normal: Vec3,
     pub material: &'a Arc<dyn Material>,
     pub t: f64,
+    pub u: f64,
+    pub v: f64,
     pub front_face: bool,
+    /// Direction the surface runs along, for shapes that have one (such as
+    /// curves) and materials that care (such as hair).
+    pub tangent: Option<Vec3>,
 }
 
 impl<'a> HitRecord<'a> {
     pub fn set_face_normal(&mut self, ray: &Ray, outward_normal: &Vec3) {
-        self.front_face = dot(&ray.direction(), &*outward_normal) < 0.0;
+        self.front_face = dot(&ray.direction(), outward_normal) < 0.0;
         self.normal = if self.front_face {
             *outward_normal
         } else {
@@ -22,11 +28,55 @@ impl<'a> HitRecord<'a> {
     }
 }
 
-pub trait Hittable {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+pub trait Hittable: Send + Sync {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>>;
+
+    /// Box enclosing the shape, or `None` for unbounded shapes.
+    fn bounding_box(&self) -> Option<Aabb>;
+
+    /// Every crossing of the surface within [t_min, t_max], nearest first.
+    /// CSG needs these to tell where the ray is inside a shape, so `t_max`
+    /// should usually be infinite. The default walks along the ray calling
+    /// `hit`; shapes that know all their roots at once should override it.
+    fn hit_all(&self, ray: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord<'_>> {
+        let min_step = 1e-6 / ray.direction().length();
+        let mut hits = Vec::new();
+        let mut t = t_min;
+        while let Some(rec) = self.hit(ray, t, t_max) {
+            // The step grows with t, or far hits would stop moving it at all.
+            let next = rec.t + min_step.max(rec.t.abs() * 1e-9);
+            hits.push(rec);
+            if next.is_nan() || next <= t {
+                break;
+            }
+            t = next;
+        }
+        hits
+    }
 }
 
+pub mod aabb;
+pub mod bvh;
+pub mod capsule;
+pub mod cone;
+pub mod constant_medium;
+pub mod csg;
+pub mod cuboid;
+pub mod curve;
+pub mod cylinder;
+pub mod disk;
+pub mod displacement;
+pub mod grid_volume;
+pub mod heightfield;
+pub mod plane;
+pub mod quad;
+pub mod sdf;
 pub mod sphere;
+pub mod subdivision;
+pub mod torus;
+pub mod transformed;
+pub mod tri_mesh;
+use aabb::Aabb;
 use sphere::Sphere;
 
 pub fn sphere(center: Point3, radius: f64, material: Arc<dyn Material>) -> Sphere {
@@ -39,6 +89,8 @@ pub fn sphere(center: Point3, radius: f64, material: Arc<dyn Material>) -> Spher
 
 pub struct HittableList {
     pub objects: Vec<Arc<dyn Hittable>>,
+    /// Medium filling the space between objects, including around the camera.
+    pub atmosphere: Option<Arc<dyn Medium>>,
 }
 
 impl HittableList {
@@ -52,19 +104,16 @@ impl HittableList {
 }
 
 impl HittableList {
-    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         let mut temp_rec: Option<HitRecord> = None;
         let mut hit_anything = false;
         let mut closest_so_far = t_max;
 
         for object in &self.objects {
-            match object.hit(ray, t_min, closest_so_far) {
-                Some(rec) => {
-                    hit_anything = true;
-                    closest_so_far = rec.t;
-                    temp_rec.replace(rec);
-                }
-                None => {}
+            if let Some(rec) = object.hit(ray, t_min, closest_so_far) {
+                hit_anything = true;
+                closest_so_far = rec.t;
+                temp_rec.replace(rec);
             }
         }
         if hit_anything {
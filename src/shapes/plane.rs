@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use crate::material::Material;
+use crate::math::onb::Onb;
+use crate::math::ray::Ray;
+use crate::math::vec3::{dot, unit_vector, vec3, Point3, Vec3};
+
+use super::aabb::Aabb;
+use super::{HitRecord, Hittable};
+
+/// Infinite plane through `point`. Texture coordinates tile every unit along
+/// two axes lying in the plane.
+pub struct Plane {
+    pub point: Point3,
+    pub normal: Vec3,
+    pub material: Arc<dyn Material>,
+    axes: Onb,
+}
+
+pub fn plane(point: Point3, normal: Vec3, material: Arc<dyn Material>) -> Plane {
+    let normal = unit_vector(normal);
+    Plane {
+        point,
+        normal,
+        material,
+        axes: Onb::build_from_w(&normal),
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let denom = dot(&self.normal, &ray.direction());
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+        let t = dot(&self.normal, &(self.point - ray.origin())) / denom;
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let planar = p - self.point;
+        let mut rec = HitRecord {
+            p,
+            t,
+            u: dot(&planar, &self.axes.u).rem_euclid(1.0),
+            v: dot(&planar, &self.axes.v).rem_euclid(1.0),
+            normal: vec3(0.0, 0.0, 0.0),
+            material: &self.material,
+            front_face: false,
+            tangent: None,
+        };
+        rec.set_face_normal(ray, &self.normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+}
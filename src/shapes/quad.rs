@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use crate::material::Material;
+use crate::math::ray::Ray;
+use crate::math::vec3::{cross, dot, unit_vector, vec3, Point3, Vec3, Vector};
+
+use super::aabb::{aabb, pad, surrounding_box, Aabb};
+use super::{HitRecord, Hittable};
+
+/// Parallelogram spanned by the edges `u` and `v` from the corner `q`.
+/// The front side is the one `cross(u, v)` points to, and (u, v) texture
+/// coordinates run from 0 to 1 along the two edges.
+pub struct Quad {
+    pub q: Point3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub material: Arc<dyn Material>,
+    normal: Vec3,
+    d: f64,
+    /// Turns a point on the plane into its coordinates along `u` and `v`.
+    w: Vec3,
+}
+
+pub fn quad(q: Point3, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Quad {
+    let n = cross(&u, &v);
+    let normal = unit_vector(n);
+    Quad {
+        q,
+        u,
+        v,
+        material,
+        normal,
+        d: dot(&normal, &q),
+        w: n / n.length_squared(),
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let denom = dot(&self.normal, &ray.direction());
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+        let t = (self.d - dot(&self.normal, &ray.origin())) / denom;
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let planar = p - self.q;
+        let alpha = dot(&self.w, &cross(&planar, &self.v));
+        let beta = dot(&self.w, &cross(&self.u, &planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        let mut rec = HitRecord {
+            p,
+            t,
+            u: alpha,
+            v: beta,
+            normal: vec3(0.0, 0.0, 0.0),
+            material: &self.material,
+            front_face: false,
+            tangent: None,
+        };
+        rec.set_face_normal(ray, &self.normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let corners = [self.q + self.u, self.q + self.v, self.q + self.u + self.v];
+        let bounds = corners.iter().fold(aabb(self.q, self.q), |b, c| {
+            surrounding_box(&b, &aabb(*c, *c))
+        });
+        Some(pad(bounds))
+    }
+}
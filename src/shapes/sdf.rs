@@ -0,0 +1,263 @@
+use std::sync::Arc;
+
+use crate::material::Material;
+use crate::math::ray::Ray;
+use crate::math::vec3::{dot, unit_vector, vec3, Point3, Vec3, Vector};
+
+use super::aabb::Aabb;
+use super::sphere::get_sphere_uv;
+use super::{HitRecord, Hittable};
+
+/// Signed distance to a surface: negative inside, positive outside. Fields
+/// are built as trees from the functions below and rendered by `Sdf`.
+pub trait DistanceField: Send + Sync {
+    fn distance(&self, p: &Point3) -> f64;
+}
+
+pub struct SdfSphere {
+    pub radius: f64,
+}
+
+impl DistanceField for SdfSphere {
+    fn distance(&self, p: &Point3) -> f64 {
+        p.length() - self.radius
+    }
+}
+
+pub fn sdf_sphere(radius: f64) -> Arc<dyn DistanceField> {
+    Arc::new(SdfSphere { radius })
+}
+
+/// Box centered on the origin.
+pub struct SdfBox {
+    pub half_extents: Vec3,
+}
+
+impl DistanceField for SdfBox {
+    fn distance(&self, p: &Point3) -> f64 {
+        let q = vec3(p.x().abs(), p.y().abs(), p.z().abs()) - self.half_extents;
+        let outside = vec3(q.x().max(0.0), q.y().max(0.0), q.z().max(0.0)).length();
+        let inside = q.x().max(q.y()).max(q.z()).min(0.0);
+        outside + inside
+    }
+}
+
+pub fn sdf_box(half_extents: Vec3) -> Arc<dyn DistanceField> {
+    Arc::new(SdfBox { half_extents })
+}
+
+/// Torus around the y axis, like `shapes::torus::Torus`.
+pub struct SdfTorus {
+    pub major_radius: f64,
+    pub minor_radius: f64,
+}
+
+impl DistanceField for SdfTorus {
+    fn distance(&self, p: &Point3) -> f64 {
+        let ring = (p.x() * p.x() + p.z() * p.z()).sqrt() - self.major_radius;
+        (ring * ring + p.y() * p.y()).sqrt() - self.minor_radius
+    }
+}
+
+pub fn sdf_torus(major_radius: f64, minor_radius: f64) -> Arc<dyn DistanceField> {
+    Arc::new(SdfTorus {
+        major_radius,
+        minor_radius,
+    })
+}
+
+pub struct Translate {
+    pub field: Arc<dyn DistanceField>,
+    pub offset: Vec3,
+}
+
+impl DistanceField for Translate {
+    fn distance(&self, p: &Point3) -> f64 {
+        self.field.distance(&(*p - self.offset))
+    }
+}
+
+pub fn translate(field: Arc<dyn DistanceField>, offset: Vec3) -> Arc<dyn DistanceField> {
+    Arc::new(Translate { field, offset })
+}
+
+/// Union blended over a distance of about `smoothness`, which gives a
+/// plain union at 0.
+pub struct SmoothUnion {
+    pub first: Arc<dyn DistanceField>,
+    pub second: Arc<dyn DistanceField>,
+    pub smoothness: f64,
+}
+
+impl DistanceField for SmoothUnion {
+    fn distance(&self, p: &Point3) -> f64 {
+        let a = self.first.distance(p);
+        let b = self.second.distance(p);
+        if self.smoothness <= 0.0 {
+            return a.min(b);
+        }
+        let h = (self.smoothness - (a - b).abs()).max(0.0) / self.smoothness;
+        a.min(b) - h * h * self.smoothness * 0.25
+    }
+}
+
+pub fn smooth_union(
+    first: Arc<dyn DistanceField>,
+    second: Arc<dyn DistanceField>,
+    smoothness: f64,
+) -> Arc<dyn DistanceField> {
+    Arc::new(SmoothUnion {
+        first,
+        second,
+        smoothness,
+    })
+}
+
+/// Infinite copies of a field, one per cell of size `period` centered on
+/// the origin. A zero component leaves that axis unrepeated. The copy
+/// should fit inside its cell or the distances stop being bounds.
+pub struct Repeat {
+    pub field: Arc<dyn DistanceField>,
+    pub period: Vec3,
+}
+
+impl DistanceField for Repeat {
+    fn distance(&self, p: &Point3) -> f64 {
+        let wrap = |x: f64, period: f64| {
+            if period > 0.0 {
+                x - period * (x / period).round()
+            } else {
+                x
+            }
+        };
+        let local = vec3(
+            wrap(p.x(), self.period.x()),
+            wrap(p.y(), self.period.y()),
+            wrap(p.z(), self.period.z()),
+        );
+        self.field.distance(&local)
+    }
+}
+
+pub fn repeat(field: Arc<dyn DistanceField>, period: Vec3) -> Arc<dyn DistanceField> {
+    Arc::new(Repeat { field, period })
+}
+
+/// Rotates each slice of the field around the y axis by `rate` radians per
+/// unit of height. This stretches distances, so shapes using it need a
+/// smaller `Sdf::step_scale`.
+pub struct Twist {
+    pub field: Arc<dyn DistanceField>,
+    pub rate: f64,
+}
+
+impl DistanceField for Twist {
+    fn distance(&self, p: &Point3) -> f64 {
+        let (sin, cos) = (self.rate * p.y()).sin_cos();
+        let local = vec3(cos * p.x() + sin * p.z(), p.y(), -sin * p.x() + cos * p.z());
+        self.field.distance(&local)
+    }
+}
+
+pub fn twist(field: Arc<dyn DistanceField>, rate: f64) -> Arc<dyn DistanceField> {
+    Arc::new(Twist { field, rate })
+}
+
+const MAX_STEPS: usize = 512;
+/// Distance at which the march counts as touching the surface.
+const SURFACE_EPSILON: f64 = 1e-5;
+
+/// Renders a distance field by sphere tracing within `bounds`, which must
+/// contain the whole surface. UVs are a spherical mapping of the normal.
+pub struct Sdf {
+    pub field: Arc<dyn DistanceField>,
+    pub bounds: Aabb,
+    pub material: Arc<dyn Material>,
+    /// Fraction of the distance bound taken per step. Lower it below 1 for
+    /// fields that overestimate distances, such as twists.
+    pub step_scale: f64,
+}
+
+pub fn sdf(field: Arc<dyn DistanceField>, bounds: Aabb, material: Arc<dyn Material>) -> Sdf {
+    Sdf {
+        field,
+        bounds,
+        material,
+        step_scale: 1.0,
+    }
+}
+
+impl Sdf {
+    /// Gradient by central differences on a tetrahedron, which takes four
+    /// evaluations instead of six.
+    fn normal(&self, p: &Point3) -> Vec3 {
+        let h = 1e-4;
+        let offsets = [
+            vec3(1.0, -1.0, -1.0),
+            vec3(-1.0, -1.0, 1.0),
+            vec3(-1.0, 1.0, -1.0),
+            vec3(1.0, 1.0, 1.0),
+        ];
+        let gradient = offsets.iter().fold(vec3(0.0, 0.0, 0.0), |sum, &k| {
+            sum + k * self.field.distance(&(*p + k * h))
+        });
+        unit_vector(gradient)
+    }
+}
+
+impl Hittable for Sdf {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (t_enter, t_exit) = self.bounds.hit(ray, t_min, t_max)?;
+        let speed = ray.direction().length();
+
+        // Marching from inside the surface (e.g. through glass) follows the
+        // distance to the way out instead. A ray starting on the surface has
+        // to get clear of it before it can hit anything.
+        let start = ray.at(t_enter);
+        let start_distance = self.field.distance(&start);
+        let mut clear = start_distance.abs() >= SURFACE_EPSILON;
+        let side = if clear {
+            start_distance.signum()
+        } else {
+            dot(&self.normal(&start), &ray.direction()).signum()
+        };
+
+        let mut t = t_enter;
+        let mut converged = false;
+        for _ in 0..MAX_STEPS {
+            let distance = side * self.field.distance(&ray.at(t));
+            if clear && distance < SURFACE_EPSILON {
+                converged = true;
+                break;
+            }
+            clear = clear || distance >= 2.0 * SURFACE_EPSILON;
+            t += self.step_scale * distance.max(SURFACE_EPSILON) / speed;
+            if t > t_exit {
+                return None;
+            }
+        }
+        if !converged {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let outward_normal = self.normal(&p);
+        let (u, v) = get_sphere_uv(&outward_normal);
+        let mut rec = HitRecord {
+            p,
+            t,
+            u,
+            v,
+            normal: vec3(0.0, 0.0, 0.0),
+            material: &self.material,
+            front_face: false,
+            tangent: None,
+        };
+        rec.set_face_normal(ray, &outward_normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
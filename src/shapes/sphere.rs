@@ -1,9 +1,11 @@
 use std::sync::Arc;
 
 use crate::material::Material;
+use crate::math::constants::PI;
 use crate::math::ray::Ray;
-use crate::math::vec3::{dot, vec3, Point3, Vec3, Vector};
+use crate::math::vec3::{dot, lerp, vec3, Point3, Vec3, Vector};
 
+use super::aabb::{aabb, surrounding_box, Aabb};
 use super::{HitRecord, Hittable};
 
 pub struct Sphere {
@@ -13,35 +15,165 @@ pub struct Sphere {
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let oc = ray.origin() - self.center;
-        let a = ray.direction().length_squared();
-        let half_b = dot(&oc, &ray.direction());
-        let c = oc.length_squared() - self.radius * self.radius;
-        let discriminant = half_b * half_b - a * c;
-        if discriminant < 0.0 {
-            return None;
-        };
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_sphere(&self.center, self.radius, &self.material, ray, t_min, t_max)
+    }
+
+    fn hit_all(&self, ray: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord<'_>> {
+        hit_all_sphere(&self.center, self.radius, &self.material, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(sphere_box(&self.center, self.radius))
+    }
+}
 
-        let sqrt = discriminant.sqrt();
+/// Sphere whose center moves in a straight line from `center0` at `time0`
+/// to `center1` at `time1`, and stays put outside that interval.
+pub struct MovingSphere {
+    pub center0: Point3,
+    pub center1: Point3,
+    pub time0: f64,
+    pub time1: f64,
+    pub radius: f64,
+    pub material: Arc<dyn Material>,
+}
+
+pub fn moving_sphere(
+    center0: Point3,
+    center1: Point3,
+    time0: f64,
+    time1: f64,
+    radius: f64,
+    material: Arc<dyn Material>,
+) -> MovingSphere {
+    MovingSphere {
+        center0,
+        center1,
+        time0,
+        time1,
+        radius,
+        material,
+    }
+}
+
+impl MovingSphere {
+    pub fn center(&self, time: f64) -> Point3 {
+        if self.time1 <= self.time0 {
+            return self.center0;
+        }
+        let t = ((time - self.time0) / (self.time1 - self.time0)).clamp(0.0, 1.0);
+        lerp(self.center0, self.center1, t)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let center = self.center(ray.time());
+        hit_sphere(&center, self.radius, &self.material, ray, t_min, t_max)
+    }
 
-        let mut root = (-half_b - sqrt) / a;
+    fn hit_all(&self, ray: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord<'_>> {
+        let center = self.center(ray.time());
+        hit_all_sphere(&center, self.radius, &self.material, ray, t_min, t_max)
+    }
+
+    /// Box swept by the sphere over its whole motion.
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(surrounding_box(
+            &sphere_box(&self.center0, self.radius),
+            &sphere_box(&self.center1, self.radius),
+        ))
+    }
+}
+
+/// Both roots of the ray/sphere quadratic, nearest first.
+fn roots(center: &Point3, radius: f64, ray: &Ray) -> Option<(f64, f64)> {
+    let oc = ray.origin() - *center;
+    let a = ray.direction().length_squared();
+    let half_b = dot(&oc, &ray.direction());
+    let c = oc.length_squared() - radius * radius;
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    };
+
+    let sqrt = discriminant.sqrt();
+    Some(((-half_b - sqrt) / a, (-half_b + sqrt) / a))
+}
+
+fn record<'a>(
+    center: &Point3,
+    radius: f64,
+    material: &'a Arc<dyn Material>,
+    ray: &Ray,
+    root: f64,
+) -> HitRecord<'a> {
+    let mut rec = HitRecord {
+        p: ray.at(root),
+        t: root,
+        u: 0.0,
+        v: 0.0,
+        normal: vec3(0.0, 0.0, 0.0),
+        material,
+        front_face: false,
+        tangent: None,
+    };
+    let outward_normal: Vec3 = (rec.p - *center) / radius;
+    rec.set_face_normal(ray, &outward_normal);
+    let (u, v) = get_sphere_uv(&outward_normal);
+    rec.u = u;
+    rec.v = v;
+    rec
+}
+
+fn hit_sphere<'a>(
+    center: &Point3,
+    radius: f64,
+    material: &'a Arc<dyn Material>,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let (near, far) = roots(center, radius, ray)?;
+
+    let mut root = near;
+    if root < t_min || t_max < root {
+        root = far;
         if root < t_min || t_max < root {
-            root = (-half_b + sqrt) / a;
-            if root < t_min || t_max < root {
-                return None;
-            }
+            return None;
         }
+    }
+    Some(record(center, radius, material, ray, root))
+}
 
-        let mut rec: Option<HitRecord> = Some(HitRecord {
-            p: ray.at(root),
-            t: root,
-            normal: vec3(0.0, 0.0, 0.0),
-            material: &self.material,
-            front_face: false,
-        });
-        let outward_normal: Vec3 = (rec.as_ref().unwrap().p - self.center) / self.radius;
-        rec.as_mut().unwrap().set_face_normal(ray, &outward_normal);
-        rec
+fn hit_all_sphere<'a>(
+    center: &Point3,
+    radius: f64,
+    material: &'a Arc<dyn Material>,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Vec<HitRecord<'a>> {
+    match roots(center, radius, ray) {
+        Some((near, far)) => [near, far]
+            .iter()
+            .filter(|&&root| t_min <= root && root <= t_max)
+            .map(|&root| record(center, radius, material, ray, root))
+            .collect(),
+        None => vec![],
     }
 }
+
+fn sphere_box(center: &Point3, radius: f64) -> Aabb {
+    let extent = vec3(radius, radius, radius);
+    aabb(*center - extent, *center + extent)
+}
+
+/// Maps a point on the unit sphere to (u, v), with u going around the y axis
+/// starting at -x and v running from the bottom pole to the top.
+pub fn get_sphere_uv(p: &Point3) -> (f64, f64) {
+    let theta = (-p.y()).acos();
+    let phi = (-p.z()).atan2(p.x()) + PI;
+    (phi / (2.0 * PI), theta / PI)
+}
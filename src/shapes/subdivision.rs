@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use crate::math::constants::PI;
+use crate::math::vec3::{cross, unit_vector, vec3, Vec3, Vector};
+
+use super::tri_mesh::MeshData;
+
+// Loop subdivision of triangle meshes, run once before a mesh is handed to
+// `tri_mesh`. Each level splits every triangle into four and smooths the
+// vertices, converging on a smooth surface through the control cage.
+
+/// An edge of the control mesh given a crease `sharpness`: the number of
+/// levels it stays sharp for before smoothing out, `f64::INFINITY` for a
+/// permanent crease. Fractional values blend between sharp and smooth.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Crease {
+    pub edge: [usize; 2],
+    pub sharpness: f64,
+}
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+/// Subdivides `mesh` `levels` times. Boundary edges are always treated as
+/// sharp. Texture coordinates are interpolated linearly and the result gets
+/// smooth vertex normals in place of any it had.
+pub fn loop_subdivide(mesh: &MeshData, creases: &[Crease], levels: usize) -> MeshData {
+    let mut positions = mesh.positions.clone();
+    let mut indices = mesh.indices.clone();
+    let mut uvs = mesh.uvs.clone();
+    let mut sharpness: HashMap<(usize, usize), f64> = creases
+        .iter()
+        .map(|crease| (edge_key(crease.edge[0], crease.edge[1]), crease.sharpness))
+        .collect();
+
+    for _ in 0..levels {
+        let level = subdivide_once(&positions, &indices, uvs.as_deref(), &sharpness);
+        positions = level.positions;
+        indices = level.indices;
+        uvs = level.uvs;
+        sharpness = level.sharpness;
+    }
+
+    let normals = vertex_normals(&positions, &indices);
+    MeshData {
+        positions,
+        indices,
+        normals: Some(normals),
+        uvs,
+    }
+}
+
+/// Area weighted average of the normals of the faces around each vertex.
+pub fn vertex_normals(positions: &[Vec3], indices: &[[usize; 3]]) -> Vec<Vec3> {
+    let mut normals = vec![vec3(0.0, 0.0, 0.0); positions.len()];
+    for &[a, b, c] in indices {
+        // Twice the area, pointing out of the front face.
+        let face_normal = cross(
+            &(positions[b] - positions[a]),
+            &(positions[c] - positions[a]),
+        );
+        for i in [a, b, c] {
+            normals[i] += face_normal;
+        }
+    }
+    normals
+        .into_iter()
+        .map(|n| {
+            if n.length_squared() > 0.0 {
+                unit_vector(n)
+            } else {
+                vec3(0.0, 1.0, 0.0)
+            }
+        })
+        .collect()
+}
+
+struct Level {
+    positions: Vec<Vec3>,
+    indices: Vec<[usize; 3]>,
+    uvs: Option<Vec<(f64, f64)>>,
+    sharpness: HashMap<(usize, usize), f64>,
+}
+
+fn subdivide_once(
+    positions: &[Vec3],
+    indices: &[[usize; 3]],
+    uvs: Option<&[(f64, f64)]>,
+    sharpness: &HashMap<(usize, usize), f64>,
+) -> Level {
+    // For every edge, the vertices facing it in its one or two triangles.
+    let mut opposite: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for &[a, b, c] in indices {
+        for (from, to, across) in [(a, b, c), (b, c, a), (c, a, b)] {
+            opposite.entry(edge_key(from, to)).or_default().push(across);
+        }
+    }
+    let edge_sharpness = |key: &(usize, usize)| {
+        if opposite[key].len() != 2 {
+            f64::INFINITY
+        } else {
+            sharpness.get(key).copied().unwrap_or(0.0)
+        }
+    };
+
+    let mut neighbours: Vec<Vec<usize>> = vec![vec![]; positions.len()];
+    for &(a, b) in opposite.keys() {
+        neighbours[a].push(b);
+        neighbours[b].push(a);
+    }
+
+    // Old vertices move towards a weighted average of their neighbours, or
+    // along their creases, or stay put at corners.
+    let mut new_positions: Vec<Vec3> = (0..positions.len())
+        .map(|v| {
+            let around = &neighbours[v];
+            let n = around.len();
+            if n == 0 {
+                return positions[v];
+            }
+            let beta = {
+                let c = 3.0 / 8.0 + 0.25 * (2.0 * PI / n as f64).cos();
+                (5.0 / 8.0 - c * c) / n as f64
+            };
+            let smooth = (1.0 - n as f64 * beta) * positions[v]
+                + beta
+                    * around
+                        .iter()
+                        .fold(vec3(0.0, 0.0, 0.0), |sum, &u| sum + positions[u]);
+
+            let sharp: Vec<(usize, f64)> = around
+                .iter()
+                .map(|&u| (u, edge_sharpness(&edge_key(v, u))))
+                .filter(|&(_, s)| s > 0.0)
+                .collect();
+            let blend = (sharp.iter().map(|&(_, s)| s.min(1.0)).sum::<f64>()
+                / sharp.len().max(1) as f64)
+                .min(1.0);
+            let sharp_position = match sharp.len() {
+                0 | 1 => return smooth,
+                2 => 0.75 * positions[v] + 0.125 * (positions[sharp[0].0] + positions[sharp[1].0]),
+                _ => positions[v],
+            };
+            smooth + blend * (sharp_position - smooth)
+        })
+        .collect();
+
+    // One new vertex per edge, from the edge's ends and the vertices facing it.
+    let mut edge_vertex: HashMap<(usize, usize), usize> = HashMap::new();
+    let mut new_uvs = uvs.map(|uvs| uvs.to_vec());
+    let mut keys: Vec<&(usize, usize)> = opposite.keys().collect();
+    keys.sort();
+    for key in keys {
+        let (a, b) = *key;
+        let middle = 0.5 * (positions[a] + positions[b]);
+        let s = edge_sharpness(key);
+        let position = if s >= 1.0 {
+            middle
+        } else {
+            let facing = &opposite[key];
+            let smooth = 0.375 * (positions[a] + positions[b])
+                + 0.125 * (positions[facing[0]] + positions[facing[1]]);
+            smooth + s * (middle - smooth)
+        };
+        edge_vertex.insert(*key, new_positions.len());
+        new_positions.push(position);
+        if let (Some(new_uvs), Some(uvs)) = (new_uvs.as_mut(), uvs) {
+            new_uvs.push((0.5 * (uvs[a].0 + uvs[b].0), 0.5 * (uvs[a].1 + uvs[b].1)));
+        }
+    }
+
+    let mut new_indices = Vec::with_capacity(4 * indices.len());
+    for &[a, b, c] in indices {
+        let ab = edge_vertex[&edge_key(a, b)];
+        let bc = edge_vertex[&edge_key(b, c)];
+        let ca = edge_vertex[&edge_key(c, a)];
+        new_indices.push([a, ab, ca]);
+        new_indices.push([ab, b, bc]);
+        new_indices.push([ca, bc, c]);
+        new_indices.push([ab, bc, ca]);
+    }
+
+    // Each crease carries on into both halves of its edge, one level softer.
+    let mut new_sharpness = HashMap::new();
+    for (&(a, b), &s) in sharpness {
+        if s > 1.0 && opposite.contains_key(&(a, b)) {
+            let middle = edge_vertex[&(a, b)];
+            new_sharpness.insert(edge_key(a, middle), s - 1.0);
+            new_sharpness.insert(edge_key(middle, b), s - 1.0);
+        }
+    }
+
+    Level {
+        positions: new_positions,
+        indices: new_indices,
+        uvs: new_uvs,
+        sharpness: new_sharpness,
+    }
+}
@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use crate::material::Material;
+use crate::math::constants::PI;
+use crate::math::ray::Ray;
+use crate::math::roots::solve_quartic;
+use crate::math::vec3::{dot, unit_vector, vec3, Point3, Vector};
+
+use super::aabb::{aabb, Aabb};
+use super::cylinder::azimuth_u;
+use super::{HitRecord, Hittable};
+
+/// Torus around the y axis through `center`. The tube of `minor_radius`
+/// follows a circle of `major_radius` in the xz plane. u goes around the
+/// y axis and v around the tube, starting on the outside.
+pub struct Torus {
+    pub center: Point3,
+    pub major_radius: f64,
+    pub minor_radius: f64,
+    pub material: Arc<dyn Material>,
+}
+
+pub fn torus(
+    center: Point3,
+    major_radius: f64,
+    minor_radius: f64,
+    material: Arc<dyn Material>,
+) -> Torus {
+    Torus {
+        center,
+        major_radius,
+        minor_radius,
+        material,
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        // The quartic is badly conditioned far from the torus, so it is
+        // solved along a unit direction starting where the ray enters the
+        // bounding box.
+        let (t_enter, _) = self.bounding_box()?.hit(ray, t_min, t_max)?;
+        let scale = ray.direction().length();
+        let d = ray.direction() / scale;
+        let o = ray.at(t_enter) - self.center;
+
+        let r2 = self.major_radius * self.major_radius;
+        let n = dot(&o, &d);
+        let k = o.length_squared() + r2 - self.minor_radius * self.minor_radius;
+        let roots = solve_quartic(
+            4.0 * n,
+            4.0 * n * n + 2.0 * k - 4.0 * r2 * (d.x() * d.x() + d.z() * d.z()),
+            4.0 * n * k - 8.0 * r2 * (o.x() * d.x() + o.z() * d.z()),
+            k * k - 4.0 * r2 * (o.x() * o.x() + o.z() * o.z()),
+        );
+        let t = roots
+            .into_iter()
+            .map(|s| t_enter + s / scale)
+            .find(|&t| t >= t_min && t <= t_max)?;
+
+        let p = ray.at(t);
+        let local = p - self.center;
+        // The tube's center line point nearest to the hit.
+        let ring = unit_vector(vec3(local.x(), 0.0, local.z())) * self.major_radius;
+        let outward_normal = (local - ring) / self.minor_radius;
+        let outward = (local.x() * local.x() + local.z() * local.z()).sqrt() - self.major_radius;
+        let tube_angle = local.y().atan2(outward);
+
+        let mut rec = HitRecord {
+            p,
+            t,
+            u: azimuth_u(local.x(), local.z()),
+            v: tube_angle.rem_euclid(2.0 * PI) / (2.0 * PI),
+            normal: vec3(0.0, 0.0, 0.0),
+            material: &self.material,
+            front_face: false,
+            tangent: None,
+        };
+        rec.set_face_normal(ray, &unit_vector(outward_normal));
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let outer = self.major_radius + self.minor_radius;
+        let extent = vec3(outer, self.minor_radius, outer);
+        Some(aabb(self.center - extent, self.center + extent))
+    }
+}
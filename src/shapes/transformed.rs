@@ -0,0 +1,236 @@
+use std::sync::Arc;
+
+use crate::math::matrix::Mat4;
+use crate::math::quaternion::Quaternion;
+use crate::math::ray::{new_ray, Ray};
+use crate::math::vec3::{lerp, unit_vector, vec3, Vec3, Vector};
+
+use super::aabb::{aabb, surrounding_box, Aabb};
+use super::{HitRecord, Hittable};
+
+/// An instance of another shape placed in the world by an affine transform.
+/// Rays are taken into the object's space, so the same `object` can be shared
+/// by many instances without copying it.
+pub struct Transformed {
+    pub object: Arc<dyn Hittable>,
+    /// Object to world transform.
+    pub transform: Mat4,
+    inverse: Mat4,
+    /// Inverse transpose, which carries normals to world space.
+    normal_transform: Mat4,
+}
+
+/// Places `object` in the world with the object to world `transform`, or
+/// `None` if the transform is not invertible (a zero scale, say).
+pub fn transformed(object: Arc<dyn Hittable>, transform: Mat4) -> Option<Transformed> {
+    let inverse = transform.inverse()?;
+    Some(Transformed {
+        object,
+        transform,
+        inverse,
+        normal_transform: inverse.transpose(),
+    })
+}
+
+/// The ray in object space. The direction is not renormalised, so t means
+/// the same in both spaces.
+fn to_object(inverse: &Mat4, ray: &Ray) -> Ray {
+    new_ray(
+        &inverse.transform_point(&ray.origin()),
+        &inverse.transform_vector(&ray.direction()),
+        ray.time(),
+    )
+}
+
+fn to_world<'a>(
+    transform: &Mat4,
+    normal_transform: &Mat4,
+    mut rec: HitRecord<'a>,
+) -> HitRecord<'a> {
+    rec.p = transform.transform_point(&rec.p);
+    // The normal already faces against the ray, and that survives the transform.
+    rec.normal = unit_vector(normal_transform.transform_vector(&rec.normal));
+    rec.tangent = rec
+        .tangent
+        .map(|tangent| unit_vector(transform.transform_vector(&tangent)));
+    rec
+}
+
+/// Box around the transformed corners of `object_box`.
+fn transformed_box(transform: &Mat4, object_box: &Aabb) -> Aabb {
+    let corners = object_box
+        .corners()
+        .map(|corner| transform.transform_point(&corner));
+    corners[1..]
+        .iter()
+        .fold(aabb(corners[0], corners[0]), |bounds, corner| {
+            surrounding_box(&bounds, &aabb(*corner, *corner))
+        })
+}
+
+impl Hittable for Transformed {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let rec = self
+            .object
+            .hit(&to_object(&self.inverse, ray), t_min, t_max)?;
+        Some(to_world(&self.transform, &self.normal_transform, rec))
+    }
+
+    fn hit_all(&self, ray: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord<'_>> {
+        self.object
+            .hit_all(&to_object(&self.inverse, ray), t_min, t_max)
+            .into_iter()
+            .map(|rec| to_world(&self.transform, &self.normal_transform, rec))
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(transformed_box(
+            &self.transform,
+            &self.object.bounding_box()?,
+        ))
+    }
+}
+
+/// Scale, then rotation, then translation. Unlike a matrix, two poses can
+/// be blended without shearing the object in between.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Pose {
+    pub translation: Vec3,
+    pub rotation: Quaternion,
+    pub scale: Vec3,
+}
+
+pub fn pose(translation: Vec3, rotation: Quaternion, scale: Vec3) -> Pose {
+    Pose {
+        translation,
+        rotation,
+        scale,
+    }
+}
+
+impl Pose {
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::translation(&self.translation)
+            * self.rotation.to_matrix()
+            * Mat4::scaling(&self.scale)
+    }
+
+    /// Inverse of `matrix`, built directly from the parts.
+    pub fn inverse_matrix(&self) -> Mat4 {
+        let inverse_scale = vec3(
+            1.0 / self.scale.x(),
+            1.0 / self.scale.y(),
+            1.0 / self.scale.z(),
+        );
+        Mat4::scaling(&inverse_scale)
+            * self.rotation.conjugate().to_matrix()
+            * Mat4::translation(&-self.translation)
+    }
+
+    /// Blends translation and scale linearly and rotation along the shortest arc.
+    pub fn interpolate(&self, other: &Pose, t: f64) -> Pose {
+        Pose {
+            translation: lerp(self.translation, other.translation, t),
+            rotation: self.rotation.slerp(&other.rotation, t),
+            scale: lerp(self.scale, other.scale, t),
+        }
+    }
+}
+
+/// Number of poses the bounding box is sampled at over the motion.
+const MOTION_BOX_SAMPLES: usize = 16;
+
+/// An instance moving from pose `start` at `time0` to `end` at `time1`,
+/// holding still outside that interval. Each ray sees the object where it
+/// is at the ray's time.
+pub struct AnimatedTransformed {
+    pub object: Arc<dyn Hittable>,
+    pub start: Pose,
+    pub end: Pose,
+    pub time0: f64,
+    pub time1: f64,
+}
+
+pub fn animated_transformed(
+    object: Arc<dyn Hittable>,
+    start: Pose,
+    end: Pose,
+    time0: f64,
+    time1: f64,
+) -> AnimatedTransformed {
+    AnimatedTransformed {
+        object,
+        start,
+        end,
+        time0,
+        time1,
+    }
+}
+
+impl AnimatedTransformed {
+    pub fn pose(&self, time: f64) -> Pose {
+        if self.time1 <= self.time0 {
+            return self.start;
+        }
+        let t = ((time - self.time0) / (self.time1 - self.time0)).clamp(0.0, 1.0);
+        self.start.interpolate(&self.end, t)
+    }
+}
+
+impl Hittable for AnimatedTransformed {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let pose = self.pose(ray.time());
+        let inverse = pose.inverse_matrix();
+        let rec = self.object.hit(&to_object(&inverse, ray), t_min, t_max)?;
+        Some(to_world(&pose.matrix(), &inverse.transpose(), rec))
+    }
+
+    fn hit_all(&self, ray: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord<'_>> {
+        let pose = self.pose(ray.time());
+        let inverse = pose.inverse_matrix();
+        let (transform, normal_transform) = (pose.matrix(), inverse.transpose());
+        self.object
+            .hit_all(&to_object(&inverse, ray), t_min, t_max)
+            .into_iter()
+            .map(|rec| to_world(&transform, &normal_transform, rec))
+            .collect()
+    }
+
+    /// Union of the boxes at evenly spaced poses along the motion, padded
+    /// by how far a rotating corner can stray from the chord between two
+    /// samples.
+    fn bounding_box(&self) -> Option<Aabb> {
+        let object_box = self.object.bounding_box()?;
+        let box_at = |i: usize| {
+            let pose = self
+                .start
+                .interpolate(&self.end, i as f64 / MOTION_BOX_SAMPLES as f64);
+            transformed_box(&pose.matrix(), &object_box)
+        };
+        let swept = (1..=MOTION_BOX_SAMPLES)
+            .fold(box_at(0), |bounds, i| surrounding_box(&bounds, &box_at(i)));
+
+        let angle = 2.0
+            * self
+                .start
+                .rotation
+                .dot(&self.end.rotation)
+                .abs()
+                .min(1.0)
+                .acos();
+        let step_angle = angle / MOTION_BOX_SAMPLES as f64;
+        let max_scale = [self.start.scale, self.end.scale]
+            .iter()
+            .map(|s| s.x().abs().max(s.y().abs()).max(s.z().abs()))
+            .fold(0.0, f64::max);
+        let reach = object_box
+            .corners()
+            .iter()
+            .map(|corner| corner.length())
+            .fold(0.0, f64::max);
+        let pad = reach * max_scale * (1.0 - (0.5 * step_angle).cos());
+        let padding = vec3(pad, pad, pad);
+        Some(aabb(swept.minimum - padding, swept.maximum + padding))
+    }
+}
@@ -2,45 +2,144 @@ use std::sync::Arc;
 
 use crate::material::Material;
 use crate::math::ray::Ray;
-use crate::math::vec3::{dot, vec3, Point3, Vec3, Vector};
+use crate::math::vec3::{cross, dot, unit_vector, vec3, Point3, Vec3};
 
+use super::aabb::{aabb, pad, surrounding_box, Aabb};
+use super::bvh::BvhTree;
 use super::{HitRecord, Hittable};
 
+/// Indexed triangle geometry. Per-vertex normals and texture coordinates are
+/// optional; without normals the mesh is flat shaded and without UVs the
+/// barycentric coordinates are reported instead.
+pub struct MeshData {
+    pub positions: Vec<Point3>,
+    pub indices: Vec<[usize; 3]>,
+    pub normals: Option<Vec<Vec3>>,
+    pub uvs: Option<Vec<(f64, f64)>>,
+}
+
+/// A triangle mesh with its own BVH over the triangles. Meant to be shared
+/// between instances with `transformed`, which places the same mesh several
+/// times without rebuilding its hierarchy.
 pub struct TriMesh {
-    pub points: [Point3; 3],
+    pub data: MeshData,
     pub material: Arc<dyn Material>,
+    tree: BvhTree,
 }
 
-impl Hittable for TriMesh {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let oc = ray.origin() - self.center;
-        let a = ray.direction().length_squared();
-        let half_b = dot(&oc, &ray.direction());
-        let c = oc.length_squared() - self.radius * self.radius;
-        let discriminant = half_b * half_b - a * c;
-        if discriminant < 0.0 {
-            return None;
-        };
-
-        let sqrt = discriminant.sqrt();
+pub fn tri_mesh(data: MeshData, material: Arc<dyn Material>) -> TriMesh {
+    let boxes: Vec<Aabb> = data
+        .indices
+        .iter()
+        .map(|&[a, b, c]| {
+            let [a, b, c] = [data.positions[a], data.positions[b], data.positions[c]];
+            pad(surrounding_box(
+                &aabb(a, a),
+                &surrounding_box(&aabb(b, b), &aabb(c, c)),
+            ))
+        })
+        .collect();
+    TriMesh {
+        tree: BvhTree::build(&boxes),
+        data,
+        material,
+    }
+}
 
-        let mut root = (-half_b - sqrt) / a;
-        if root < t_min || t_max < root {
-            root = (-half_b + sqrt) / a;
-            if root < t_min || t_max < root {
-                return None;
-            }
-        }
+impl TriMesh {
+    /// Möller–Trumbore intersection with a single triangle.
+    fn hit_triangle(
+        &self,
+        triangle: usize,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<HitRecord<'_>> {
+        let [i0, i1, i2] = self.data.indices[triangle];
+        let p0 = self.data.positions[i0];
+        let edge1 = self.data.positions[i1] - p0;
+        let edge2 = self.data.positions[i2] - p0;
+        let (t, b1, b2) = intersect_triangle(ray, &p0, &edge1, &edge2, t_min, t_max)?;
+        let b0 = 1.0 - b1 - b2;
 
-        let mut rec: Option<HitRecord> = Some(HitRecord {
-            p: ray.at(root),
-            t: root,
+        let (u, v) = match &self.data.uvs {
+            Some(uvs) => (
+                b0 * uvs[i0].0 + b1 * uvs[i1].0 + b2 * uvs[i2].0,
+                b0 * uvs[i0].1 + b1 * uvs[i1].1 + b2 * uvs[i2].1,
+            ),
+            None => (b1, b2),
+        };
+        let mut rec = HitRecord {
+            p: ray.at(t),
+            t,
+            u,
+            v,
             normal: vec3(0.0, 0.0, 0.0),
             material: &self.material,
             front_face: false,
-        });
-        let outward_normal: Vec3 = (rec.as_ref().unwrap().p - self.center) / self.radius;
-        rec.as_mut().unwrap().set_face_normal(ray, &outward_normal);
-        rec
+            tangent: None,
+        };
+        // The side is decided by the geometric normal, the interpolated
+        // normal is only used for shading and is flipped to the same side.
+        let geometric_normal = unit_vector(cross(&edge1, &edge2));
+        rec.set_face_normal(ray, &geometric_normal);
+        if let Some(normals) = &self.data.normals {
+            let shading_normal =
+                unit_vector(normals[i0] * b0 + normals[i1] * b1 + normals[i2] * b2);
+            rec.normal = if dot(&shading_normal, &rec.normal) < 0.0 {
+                -shading_normal
+            } else {
+                shading_normal
+            };
+        }
+        Some(rec)
+    }
+}
+
+impl Hittable for TriMesh {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.tree
+            .hit(ray, t_min, t_max, |triangle, closest_so_far| {
+                self.hit_triangle(triangle, ray, t_min, closest_so_far)
+            })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.tree.bounds()
+    }
+}
+
+/// Möller–Trumbore intersection with the triangle spanned by `edge1` and
+/// `edge2` from `p0`. Returns t and the barycentric weights of the second
+/// and third corners.
+pub fn intersect_triangle(
+    ray: &Ray,
+    p0: &Point3,
+    edge1: &Vec3,
+    edge2: &Vec3,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64, f64)> {
+    let pvec = cross(&ray.direction(), edge2);
+    let det = dot(edge1, &pvec);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = ray.origin() - *p0;
+    let b1 = dot(&tvec, &pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec = cross(&tvec, edge1);
+    let b2 = dot(&ray.direction(), &qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    let t = dot(edge2, &qvec) * inv_det;
+    if t < t_min || t_max < t {
+        return None;
     }
+    Some((t, b1, b2))
 }
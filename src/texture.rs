@@ -0,0 +1,73 @@
+use std::io::Result;
+use std::sync::Arc;
+
+use crate::load::{read_pnm, Image};
+use crate::math::base::clamp;
+use crate::math::vec3::{color, ColorRGB, Point3, Vector};
+
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> ColorRGB;
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct SolidColor {
+    pub color: ColorRGB,
+}
+
+impl Texture for SolidColor {
+    #[allow(unused_variables)]
+    fn value(&self, u: f64, v: f64, p: &Point3) -> ColorRGB {
+        self.color
+    }
+}
+
+pub fn solid_color(color: ColorRGB) -> Arc<dyn Texture> {
+    Arc::new(SolidColor { color })
+}
+
+/// 3D checker pattern alternating between two textures every `1 / frequency` units.
+pub struct Checker {
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+    pub frequency: f64,
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> ColorRGB {
+        let sines = (self.frequency * p.x()).sin()
+            * (self.frequency * p.y()).sin()
+            * (self.frequency * p.z()).sin();
+        if sines < 0.0 {
+            self.odd.value(u, v, p)
+        } else {
+            self.even.value(u, v, p)
+        }
+    }
+}
+
+/// Nearest-neighbour lookup into an image, with v = 0 at the bottom row.
+pub struct ImageTexture {
+    pub image: Image,
+}
+
+impl ImageTexture {
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(ImageTexture {
+            image: read_pnm(path)?,
+        })
+    }
+}
+
+impl Texture for ImageTexture {
+    #[allow(unused_variables)]
+    fn value(&self, u: f64, v: f64, p: &Point3) -> ColorRGB {
+        if self.image.width == 0 || self.image.height == 0 {
+            return color(0.0, 1.0, 1.0);
+        }
+        let u = clamp(u, 0.0, 1.0);
+        let v = 1.0 - clamp(v, 0.0, 1.0);
+        let i = ((u * self.image.width as f64) as usize).min(self.image.width - 1);
+        let j = ((v * self.image.height as f64) as usize).min(self.image.height - 1);
+        self.image.pixel(i, j)
+    }
+}